@@ -344,6 +344,9 @@ impl Server {
                 forward_read_only_project_request::<proto::LanguageServerIdForName>,
             )
             .add_request_handler(forward_read_only_project_request::<proto::GetDocumentDiagnostics>)
+            .add_request_handler(
+                forward_read_only_project_request::<proto::GetLanguageServerCommands>,
+            )
             .add_request_handler(
                 forward_mutating_project_request::<proto::RegisterBufferWithLanguageServers>,
             )
@@ -362,6 +365,9 @@ impl Server {
             .add_request_handler(forward_mutating_project_request::<proto::PerformRename>)
             .add_request_handler(forward_mutating_project_request::<proto::ReloadBuffers>)
             .add_request_handler(forward_mutating_project_request::<proto::ApplyCodeActionKind>)
+            .add_request_handler(
+                forward_mutating_project_request::<proto::ExecuteLanguageServerCommand>,
+            )
             .add_request_handler(forward_mutating_project_request::<proto::FormatBuffers>)
             .add_request_handler(forward_mutating_project_request::<proto::CreateProjectEntry>)
             .add_request_handler(forward_mutating_project_request::<proto::RenameProjectEntry>)
@@ -9,7 +9,7 @@ use std::{
 
 use client::parse_zed_link;
 use command_palette_hooks::{
-    CommandInterceptResult, CommandPaletteFilter, CommandPaletteInterceptor,
+    CommandInterceptResult, CommandPaletteCommands, CommandPaletteFilter, CommandPaletteInterceptor,
 };
 
 use fuzzy::{StringMatch, StringMatchCandidate};
@@ -94,7 +94,7 @@ impl CommandPalette {
     ) -> Self {
         let filter = CommandPaletteFilter::try_global(cx);
 
-        let commands = window
+        let mut commands = window
             .available_actions(cx)
             .into_iter()
             .filter_map(|action| {
@@ -107,7 +107,16 @@ impl CommandPalette {
                     action,
                 })
             })
-            .collect();
+            .collect::<Vec<_>>();
+        commands.extend(
+            CommandPaletteCommands::commands(window, cx)
+                .into_iter()
+                .filter(|command| !filter.is_some_and(|filter| filter.is_hidden(&*command.action)))
+                .map(|command| Command {
+                    name: command.name,
+                    action: command.action,
+                }),
+        );
 
         let delegate =
             CommandPaletteDelegate::new(cx.entity().downgrade(), commands, previous_focus_handle);
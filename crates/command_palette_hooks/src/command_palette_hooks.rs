@@ -6,7 +6,7 @@ use std::any::TypeId;
 
 use collections::HashSet;
 use derive_more::{Deref, DerefMut};
-use gpui::{Action, App, BorrowAppContext, Global};
+use gpui::{Action, App, BorrowAppContext, Global, Window};
 
 /// Initializes the command palette hooks.
 pub fn init(cx: &mut App) {
@@ -152,3 +152,48 @@ impl CommandPaletteInterceptor {
         self.0 = Some(handler);
     }
 }
+
+/// A command offered in the command palette alongside the available actions.
+pub struct CommandPaletteCommand {
+    /// The name that the command is listed and matched by.
+    pub name: String,
+    /// The action dispatched when the command is selected.
+    pub action: Box<dyn Action>,
+}
+
+/// Providers of commands that depend on the state of the window, such as on the active item,
+/// and so can't be offered as actions of their own.
+#[derive(Default)]
+pub struct CommandPaletteCommands(Vec<Box<dyn Fn(&Window, &App) -> Vec<CommandPaletteCommand>>>);
+
+#[derive(Default)]
+struct GlobalCommandPaletteCommands(CommandPaletteCommands);
+
+impl Global for GlobalCommandPaletteCommands {}
+
+impl CommandPaletteCommands {
+    /// Registers a provider, which is asked for its commands whenever the command palette opens.
+    pub fn register(
+        cx: &mut App,
+        provider: impl Fn(&Window, &App) -> Vec<CommandPaletteCommand> + 'static,
+    ) {
+        cx.default_global::<GlobalCommandPaletteCommands>()
+            .0
+            .0
+            .push(Box::new(provider));
+    }
+
+    /// Returns the commands of all of the registered providers for the given window.
+    pub fn commands(window: &Window, cx: &App) -> Vec<CommandPaletteCommand> {
+        cx.try_global::<GlobalCommandPaletteCommands>()
+            .map(|commands| {
+                commands
+                    .0
+                    .0
+                    .iter()
+                    .flat_map(|provider| provider(window, cx))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -200,6 +200,14 @@ pub fn deploy_context_menu(
         });
 
         let evaluate_selection = window.is_action_available(&EvaluateSelectedText, cx);
+        let offers_workspace_commands = anchor
+            .buffer_id
+            .and_then(|buffer_id| editor.buffer().read(cx).buffer(buffer_id))
+            .is_some_and(|buffer| {
+                project.read(cx).lsp_store().update(cx, |lsp_store, cx| {
+                    lsp_store.offers_workspace_commands(&buffer, cx)
+                })
+            });
 
         ui::ContextMenu::build(window, cx, |menu, _window, _cx| {
             let builder = menu
@@ -227,6 +235,12 @@ pub fn deploy_context_menu(
                         quick_launch: false,
                     }),
                 )
+                .when(offers_workspace_commands, |builder| {
+                    builder.action(
+                        "Run LSP Workspace Command",
+                        Box::new(zed_actions::lsp_workspace_command_selector::Toggle),
+                    )
+                })
                 .separator()
                 .action("Cut", Box::new(Cut))
                 .action("Copy", Box::new(Copy))
@@ -252,9 +252,28 @@ struct AnyNotification {
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Error {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<i64>,
     message: String,
 }
 
+/// The error that a language server responded to a request with, as opposed to the request
+/// failing to reach it or to be answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseError {
+    /// The JSON-RPC error code, such as `-32602` for invalid params.
+    pub code: Option<i64>,
+    pub message: String,
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
 pub trait LspRequestFuture<O>: Future<Output = ConnectionResult<O>> {
     fn id(&self) -> i32;
 }
@@ -1036,6 +1055,7 @@ impl LanguageServer {
                                                 jsonrpc: JSON_RPC_VERSION,
                                                 id,
                                                 value: LspResult::Error(Some(Error {
+                                                    code: None,
                                                     message: error.to_string(),
                                                 })),
                                             },
@@ -1057,6 +1077,7 @@ impl LanguageServer {
                                 id,
                                 result: None,
                                 error: Some(Error {
+                                    code: None,
                                     message: error.to_string(),
                                 }),
                             };
@@ -1202,7 +1223,11 @@ impl LanguageServer {
                                             Err(error).context("failed to deserialize response")
                                         }
                                     }
-                                    Err(error) => Err(anyhow!("{}", error.message)),
+                                    Err(error) => Err(ResponseError {
+                                        code: error.code,
+                                        message: error.message,
+                                    }
+                                    .into()),
                                 };
                                 _ = tx.send(response);
                             })
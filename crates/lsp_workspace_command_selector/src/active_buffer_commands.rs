@@ -0,0 +1,148 @@
+use editor::Editor;
+use gpui::{
+    Context, Entity, IntoElement, ParentElement, Render, Subscription, Task, WeakEntity, Window,
+    div,
+};
+use language::BufferId;
+use project::{LspStore, LspStoreEvent};
+use settings::{Settings, SettingsStore};
+use ui::{Button, ButtonCommon, Clickable, FluentBuilder, LabelSize, Tooltip};
+use util::ResultExt;
+use workspace::{StatusItemView, Workspace, item::ItemHandle};
+
+use crate::{LspWorkspaceCommandSelector, LspWorkspaceCommandSettings, Toggle, discover_commands};
+
+/// A status bar button with the number of workspace commands available for the active buffer,
+/// which opens the selector.
+pub struct ActiveBufferCommands {
+    command_count: usize,
+    active_editor: Option<WeakEntity<Editor>>,
+    active_buffer: Option<BufferId>,
+    workspace: WeakEntity<Workspace>,
+    lsp_store: Entity<LspStore>,
+    count_commands: Task<()>,
+    _observe_active_editor: Option<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ActiveBufferCommands {
+    pub fn new(workspace: &Workspace, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        let subscriptions = vec![
+            cx.subscribe_in(&lsp_store, window, |this, _, event, window, cx| {
+                if let LspStoreEvent::LanguageServerAdded(..)
+                | LspStoreEvent::LanguageServerRemoved(_)
+                | LspStoreEvent::LanguageServerCapabilitiesUpdated(_) = event
+                {
+                    this.count_commands(window, cx);
+                }
+            }),
+            cx.observe_global::<SettingsStore>(|_, cx| cx.notify()),
+        ];
+        Self {
+            command_count: 0,
+            active_editor: None,
+            active_buffer: None,
+            workspace: workspace.weak_handle(),
+            lsp_store,
+            count_commands: Task::ready(()),
+            _observe_active_editor: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn update_active_buffer(
+        &mut self,
+        editor: Entity<Editor>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let active_buffer = editor
+            .read(cx)
+            .active_excerpt(cx)
+            .map(|(_, buffer, _)| buffer.read(cx).remote_id());
+        if active_buffer != self.active_buffer {
+            self.active_buffer = active_buffer;
+            self.count_commands(window, cx);
+        }
+    }
+
+    fn count_commands(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(editor) = self
+            .active_editor
+            .as_ref()
+            .and_then(|editor| editor.upgrade())
+        else {
+            self.command_count = 0;
+            cx.notify();
+            return;
+        };
+        let Some((_, active_buffer, _)) = editor.read(cx).active_excerpt(cx) else {
+            self.command_count = 0;
+            cx.notify();
+            return;
+        };
+        let buffers = LspWorkspaceCommandSelector::editor_buffers(&editor, active_buffer, cx);
+        let discovery = discover_commands(buffers, &self.lsp_store, cx);
+        self.count_commands = cx.spawn_in(window, async move |this, cx| {
+            let Some((commands, _)) = discovery.await.log_err() else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                this.command_count = commands.len();
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+}
+
+impl Render for ActiveBufferCommands {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let visible = self.command_count > 0 && LspWorkspaceCommandSettings::get_global(cx).button;
+        div().when(visible, |el| {
+            let label = match self.command_count {
+                1 => "1 LSP Command".to_string(),
+                count => format!("{count} LSP Commands"),
+            };
+            el.child(
+                Button::new("lsp-workspace-commands", label)
+                    .label_size(LabelSize::Small)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        if let Some(workspace) = this.workspace.upgrade() {
+                            workspace.update(cx, |workspace, cx| {
+                                LspWorkspaceCommandSelector::toggle(workspace, window, cx)
+                            });
+                        }
+                    }))
+                    .tooltip(|window, cx| {
+                        Tooltip::for_action("Run LSP Workspace Command", &Toggle, window, cx)
+                    }),
+            )
+        })
+    }
+}
+
+impl StatusItemView for ActiveBufferCommands {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.active_buffer = None;
+        self.command_count = 0;
+        if let Some(editor) = active_pane_item.and_then(|item| item.downcast::<Editor>()) {
+            self.active_editor = Some(editor.downgrade());
+            self._observe_active_editor =
+                Some(cx.observe_in(&editor, window, Self::update_active_buffer));
+            self.update_active_buffer(editor, window, cx);
+        } else {
+            self.active_editor = None;
+            self._observe_active_editor = None;
+            self.count_commands = Task::ready(());
+        }
+
+        cx.notify();
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::{Context as _, Result};
+use editor::Editor;
+use gpui::{App, Entity, FocusHandle, Focusable as _, Window};
+use serde_json::{Map, Number, Value};
+use ui::{Checkbox, prelude::*};
+
+use crate::{
+    command_arguments_prompt::CommandArgumentsPrompt,
+    lsp_workspace_command_settings::{ArgumentField, ArgumentFieldKind},
+};
+
+/// The labeled inputs that a command's arguments are entered with, as configured in the
+/// `argument_forms` setting.
+pub(crate) struct ArgumentForm {
+    fields: Vec<ArgumentField>,
+    inputs: Vec<FieldInput>,
+}
+
+enum FieldInput {
+    Text(Entity<Editor>),
+    Bool(bool),
+    /// The index of the chosen option.
+    Choice(usize),
+}
+
+/// The value entered for a field, read from its input.
+#[derive(Debug)]
+enum FieldValue {
+    Text(String),
+    Bool(bool),
+    Choice(usize),
+}
+
+impl ArgumentForm {
+    /// Creates the form's inputs, filled in with the values of the object that the command was
+    /// last run with.
+    pub(crate) fn new(
+        fields: Vec<ArgumentField>,
+        last_arguments: Option<&str>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let last_values = last_arguments
+            .and_then(|input| serde_json::from_str::<Vec<Value>>(input).ok())
+            .and_then(|arguments| arguments.into_iter().next())
+            .and_then(|argument| match argument {
+                Value::Object(values) => Some(values),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let inputs = fields
+            .iter()
+            .map(|field| {
+                let last_value = last_values.get(&field.name);
+                match field.kind {
+                    ArgumentFieldKind::String | ArgumentFieldKind::Number => {
+                        let text = match last_value {
+                            Some(Value::String(text)) => text.clone(),
+                            Some(Value::Number(number)) => number.to_string(),
+                            _ => String::new(),
+                        };
+                        let placeholder = match field.kind {
+                            ArgumentFieldKind::Number => "number",
+                            _ => "text",
+                        };
+                        FieldInput::Text(cx.new(|cx| {
+                            let mut editor = Editor::single_line(window, cx);
+                            editor.set_placeholder_text(placeholder, cx);
+                            editor.set_text(text, window, cx);
+                            editor
+                        }))
+                    }
+                    ArgumentFieldKind::Bool => {
+                        FieldInput::Bool(last_value.and_then(Value::as_bool).unwrap_or(false))
+                    }
+                    ArgumentFieldKind::Enum => FieldInput::Choice(
+                        last_value
+                            .and_then(Value::as_str)
+                            .and_then(|last| field.options.iter().position(|option| option == last))
+                            .unwrap_or(0),
+                    ),
+                }
+            })
+            .collect();
+        Self { fields, inputs }
+    }
+
+    /// The input to focus when the form is shown: the first of its text fields.
+    pub(crate) fn focus_handle(&self, cx: &App) -> Option<FocusHandle> {
+        self.inputs.iter().find_map(|input| match input {
+            FieldInput::Text(editor) => Some(editor.focus_handle(cx)),
+            _ => None,
+        })
+    }
+
+    /// The arguments entered in the form, as the JSON array that would be entered in the raw
+    /// arguments prompt, so that it's resolved the same way.
+    pub(crate) fn input(&self, cx: &App) -> Result<String> {
+        let values = self
+            .inputs
+            .iter()
+            .map(|input| match input {
+                FieldInput::Text(editor) => FieldValue::Text(editor.read(cx).text(cx)),
+                FieldInput::Bool(value) => FieldValue::Bool(*value),
+                FieldInput::Choice(ix) => FieldValue::Choice(*ix),
+            })
+            .collect::<Vec<_>>();
+        let argument = form_argument(&self.fields, &values)?;
+        Ok(Value::Array(vec![argument]).to_string())
+    }
+
+    fn toggle(&mut self, ix: usize, value: bool) {
+        if let Some(FieldInput::Bool(current)) = self.inputs.get_mut(ix) {
+            *current = value;
+        }
+    }
+
+    fn choose_next(&mut self, ix: usize) {
+        let option_count = self.fields.get(ix).map_or(0, |field| field.options.len());
+        if let Some(FieldInput::Choice(current)) = self.inputs.get_mut(ix) {
+            *current = (*current + 1) % option_count.max(1);
+        }
+    }
+
+    pub(crate) fn render(&self, cx: &mut Context<CommandArgumentsPrompt>) -> impl IntoElement {
+        v_flex()
+            .gap_1()
+            .children(self.fields.iter().zip(&self.inputs).enumerate().map(
+                |(ix, (field, input))| {
+                    let input = match input {
+                        FieldInput::Text(editor) => div()
+                            .flex_1()
+                            .px_1()
+                            .border_1()
+                            .rounded_sm()
+                            .border_color(cx.theme().colors().border_variant)
+                            .child(editor.clone())
+                            .into_any_element(),
+                        FieldInput::Bool(value) => {
+                            Checkbox::new(("argument-field", ix), (*value).into())
+                                .on_click(cx.listener(move |prompt, state: &ToggleState, _, cx| {
+                                    prompt
+                                        .update_form(cx, |form| form.toggle(ix, state.selected()));
+                                }))
+                                .into_any_element()
+                        }
+                        FieldInput::Choice(choice) => Button::new(
+                            ("argument-field", ix),
+                            field.options.get(*choice).cloned().unwrap_or_default(),
+                        )
+                        .icon(IconName::ChevronDown)
+                        .icon_position(IconPosition::End)
+                        .icon_size(IconSize::XSmall)
+                        .label_size(LabelSize::Small)
+                        .on_click(cx.listener(move |prompt, _, _, cx| {
+                            prompt.update_form(cx, |form| form.choose_next(ix));
+                        }))
+                        .into_any_element(),
+                    };
+                    h_flex()
+                        .gap_2()
+                        .child(
+                            div()
+                                .w(rems(8.))
+                                .child(Label::new(field.name.clone()).color(Color::Muted)),
+                        )
+                        .child(input)
+                },
+            ))
+    }
+}
+
+/// Assembles the object sent as the command's argument, leaving out the text fields that are
+/// empty.
+fn form_argument(fields: &[ArgumentField], values: &[FieldValue]) -> Result<Value> {
+    let mut argument = Map::new();
+    for (field, value) in fields.iter().zip(values) {
+        let value = match (field.kind, value) {
+            (_, FieldValue::Text(text)) if text.trim().is_empty() => continue,
+            (ArgumentFieldKind::Number, FieldValue::Text(text)) => Value::Number(
+                text.trim()
+                    .parse::<Number>()
+                    .ok()
+                    .with_context(|| format!("{} must be a number", field.name))?,
+            ),
+            (_, FieldValue::Text(text)) => Value::String(text.clone()),
+            (_, FieldValue::Bool(value)) => Value::Bool(*value),
+            (_, FieldValue::Choice(ix)) => match field.options.get(*ix) {
+                Some(option) => Value::String(option.clone()),
+                None => continue,
+            },
+        };
+        argument.insert(field.name.clone(), value);
+    }
+    Ok(Value::Object(argument))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_form_argument() {
+        let field = |name: &str, kind, options: &[&str]| ArgumentField {
+            name: name.to_string(),
+            kind,
+            options: options.iter().map(|option| option.to_string()).collect(),
+        };
+        let fields = [
+            field("query", ArgumentFieldKind::String, &[]),
+            field("limit", ArgumentFieldKind::Number, &[]),
+            field("parseOnly", ArgumentFieldKind::Bool, &[]),
+            field("scope", ArgumentFieldKind::Enum, &["file", "workspace"]),
+        ];
+        let argument = |query: &str, limit: &str| {
+            form_argument(
+                &fields,
+                &[
+                    FieldValue::Text(query.to_string()),
+                    FieldValue::Text(limit.to_string()),
+                    FieldValue::Bool(true),
+                    FieldValue::Choice(1),
+                ],
+            )
+        };
+        assert_eq!(
+            argument("foo($a) ==>> bar($a)", " 10 ").unwrap(),
+            json!({
+                "query": "foo($a) ==>> bar($a)",
+                "limit": 10,
+                "parseOnly": true,
+                "scope": "workspace",
+            })
+        );
+        assert_eq!(
+            argument("", "").unwrap(),
+            json!({ "parseOnly": true, "scope": "workspace" })
+        );
+        assert_eq!(
+            argument("", "ten").unwrap_err().to_string(),
+            "limit must be a number"
+        );
+    }
+}
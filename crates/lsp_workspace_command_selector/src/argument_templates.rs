@@ -0,0 +1,189 @@
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use serde_json::Value;
+
+/// Values for the `${name}` tokens that may appear in command arguments.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateVariables {
+    values: HashMap<&'static str, Value>,
+}
+
+impl TemplateVariables {
+    pub(crate) fn insert(&mut self, name: &'static str, value: impl Into<Value>) {
+        self.values.insert(name, value.into());
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    fn get(&self, name: &str) -> Result<&Value> {
+        self.values
+            .get(name)
+            .with_context(|| format!("`${{{name}}}` is not available here"))
+    }
+}
+
+/// Parses `input` as a JSON array of arguments, resolving template tokens along the way.
+///
+/// Tokens outside of JSON strings are replaced by the JSON representation of their value
+/// before parsing, so `["${file}", ${line}]` produces a string and a number. Tokens inside
+/// of strings are interpolated after parsing, so their values never need to be escaped.
+pub(crate) fn resolve_arguments(input: &str, variables: &TemplateVariables) -> Result<Vec<Value>> {
+    if input.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let input = substitute_bare_tokens(input, variables)?;
+    let mut arguments = serde_json::from_str::<Vec<Value>>(&input)?;
+    for argument in &mut arguments {
+        substitute_string_tokens(argument, variables)?;
+    }
+    Ok(arguments)
+}
+
+fn substitute_bare_tokens(input: &str, variables: &TemplateVariables) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut ix = 0;
+    while let Some(ch) = input[ix..].chars().next() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+        } else if ch == '"' {
+            in_string = true;
+        } else if let Some((name, token_len)) = parse_token(&input[ix..]) {
+            output.push_str(&variables.get(name)?.to_string());
+            ix += token_len;
+            continue;
+        }
+        output.push(ch);
+        ix += ch.len_utf8();
+    }
+    Ok(output)
+}
+
+fn substitute_string_tokens(value: &mut Value, variables: &TemplateVariables) -> Result<()> {
+    match value {
+        Value::String(text) if text.contains("${") => {
+            let mut output = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                output.push_str(&rest[..start]);
+                rest = &rest[start..];
+                match parse_token(rest) {
+                    Some((name, token_len)) => {
+                        match variables.get(name)? {
+                            Value::String(value) => output.push_str(value),
+                            value => output.push_str(&value.to_string()),
+                        }
+                        rest = &rest[token_len..];
+                    }
+                    None => {
+                        output.push_str("${");
+                        rest = &rest[2..];
+                    }
+                }
+            }
+            output.push_str(rest);
+            *text = output;
+        }
+        Value::Array(items) => {
+            for item in items {
+                substitute_string_tokens(item, variables)?;
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                substitute_string_tokens(item, variables)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Returns the variable name and the byte length of a `${name}` token at the start of `text`.
+fn parse_token(text: &str) -> Option<(&str, usize)> {
+    let name_and_rest = text.strip_prefix("${")?;
+    let end = name_and_rest.find('}')?;
+    let name = &name_and_rest[..end];
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    {
+        return None;
+    }
+    Some((name, end + 3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn variables() -> TemplateVariables {
+        let mut variables = TemplateVariables::default();
+        variables.insert("file", "file:///dir/a \"quoted\".rs");
+        variables.insert("line", 4);
+        variables.insert("column", 2);
+        variables.insert("selection", "");
+        variables
+    }
+
+    #[test]
+    fn test_resolve_arguments_without_templates() {
+        let variables = TemplateVariables::default();
+        assert_eq!(
+            resolve_arguments("", &variables).unwrap(),
+            Vec::<Value>::new()
+        );
+        assert_eq!(
+            resolve_arguments("  ", &variables).unwrap(),
+            Vec::<Value>::new()
+        );
+        assert_eq!(
+            resolve_arguments(
+                r#"[{"textDocument": {"uri": "file:///a.rs"}}, 3]"#,
+                &variables
+            )
+            .unwrap(),
+            vec![json!({"textDocument": {"uri": "file:///a.rs"}}), json!(3)]
+        );
+        assert!(resolve_arguments("{}", &variables).is_err());
+        assert!(resolve_arguments("[1,", &variables).is_err());
+    }
+
+    #[test]
+    fn test_resolve_arguments() {
+        let variables = variables();
+        assert_eq!(
+            resolve_arguments(r#"["${file}", ${line}]"#, &variables).unwrap(),
+            vec![json!("file:///dir/a \"quoted\".rs"), json!(4)]
+        );
+        assert_eq!(
+            resolve_arguments(
+                r#"[{"position": {"line": ${line}, "character": ${column}}, "label": "${line}:${column}"}]"#,
+                &variables
+            )
+            .unwrap(),
+            vec![json!({"position": {"line": 4, "character": 2}, "label": "4:2"})]
+        );
+        assert_eq!(
+            resolve_arguments(r#"["${selection}", "${not a token}"]"#, &variables).unwrap(),
+            vec![json!(""), json!("${not a token}")]
+        );
+        assert_eq!(
+            resolve_arguments(r#"["\"${line}\""]"#, &variables).unwrap(),
+            vec![json!("\"4\"")]
+        );
+        assert!(resolve_arguments(r#"[${unknown}]"#, &variables).is_err());
+        assert!(resolve_arguments(r#"["${unknown}"]"#, &variables).is_err());
+    }
+}
@@ -0,0 +1,690 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{Context as _, Result, anyhow};
+use collections::{HashMap, HashSet};
+use editor::{Editor, EditorEvent};
+use gpui::{
+    Action as _, App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Global, Render,
+    Subscription, Task, WeakEntity,
+};
+use language::{Buffer, BufferSnapshot, PointUtf16, range_to_lsp};
+use lsp::LanguageServerId;
+use project::{File, LspStore, ProjectPath, lsp_command::file_path_to_lsp_url};
+use serde_json::{Value, json};
+use settings::Settings;
+use ui::{KeyBinding, prelude::*};
+use util::ResultExt;
+use workspace::{ModalView, Toast, Workspace, notifications::NotificationId};
+
+use crate::{
+    LastRunCommand, LspWorkspaceCommandSelector, LspWorkspaceCommandSettings, ToggleRawArguments,
+    argument_form::ArgumentForm,
+    argument_templates::{TemplateVariables, resolve_arguments},
+    confirm_and_run_command, inspect_command_result,
+};
+
+/// The arguments last entered for each command id, kept for the rest of the session.
+#[derive(Default)]
+struct LastCommandArguments(HashMap<String, String>);
+
+impl Global for LastCommandArguments {}
+
+/// The arguments last entered for the command, unresolved.
+pub(crate) fn last_command_arguments(command: &str, cx: &App) -> Option<String> {
+    cx.try_global::<LastCommandArguments>()?
+        .0
+        .get(command)
+        .cloned()
+}
+
+/// A second stage of the selector, which collects the arguments to send with the chosen command.
+pub(crate) struct CommandArgumentsPrompt {
+    focus_handle: FocusHandle,
+    arguments_editor: Entity<Editor>,
+    /// The form configured for the command, shown instead of the arguments editor unless
+    /// `raw_arguments` is set.
+    form: Option<ArgumentForm>,
+    raw_arguments: bool,
+    workspace: WeakEntity<Workspace>,
+    active_editor: Entity<Editor>,
+    buffer: Entity<Buffer>,
+    command: String,
+    server_ids: Vec<LanguageServerId>,
+    lsp_store: Entity<LspStore>,
+    reopen_selector: bool,
+    /// Whether to open the command's result in a buffer rather than applying it.
+    inspect_result: bool,
+    /// Validates the arguments against the schema that the server advertises for them, if any.
+    arguments_validator: Option<jsonschema::Validator>,
+    error: Option<SharedString>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ModalView for CommandArgumentsPrompt {}
+
+impl EventEmitter<DismissEvent> for CommandArgumentsPrompt {}
+
+impl Focusable for CommandArgumentsPrompt {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        match self.shown_form() {
+            Some(form) => form
+                .focus_handle(cx)
+                .unwrap_or_else(|| self.focus_handle.clone()),
+            None => self.arguments_editor.focus_handle(cx),
+        }
+    }
+}
+
+impl CommandArgumentsPrompt {
+    pub(crate) fn new(
+        workspace: WeakEntity<Workspace>,
+        active_editor: Entity<Editor>,
+        buffer: Entity<Buffer>,
+        command: String,
+        server_ids: Vec<LanguageServerId>,
+        lsp_store: Entity<LspStore>,
+        reopen_selector: bool,
+        inspect_result: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let last_arguments = last_command_arguments(&command, cx);
+        let form_fields = LspWorkspaceCommandSettings::get_global(cx)
+            .argument_forms
+            .get(&command)
+            .cloned();
+        let form = form_fields
+            .map(|fields| ArgumentForm::new(fields, last_arguments.as_deref(), window, cx));
+        let arguments_validator = arguments_schema(lsp_store.read(cx), &server_ids, &command)
+            .and_then(|schema| {
+                jsonschema::validator_for(&schema)
+                    .with_context(|| format!("invalid argument schema for {command}"))
+                    .log_err()
+            });
+        let arguments_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("[]", cx);
+            if let Some(last_arguments) = last_arguments {
+                editor.set_text(last_arguments, window, cx);
+            }
+            editor
+        });
+        let focus_handle = cx.focus_handle();
+        let subscriptions = vec![
+            cx.subscribe_in(&arguments_editor, window, Self::on_arguments_editor_event),
+            // The prompt has several inputs with a form, so it's dismissed once the focus leaves
+            // all of them rather than when one of them is blurred.
+            cx.on_focus_out(&focus_handle, window, |_, _, _, cx| cx.emit(DismissEvent)),
+        ];
+
+        Self {
+            focus_handle,
+            arguments_editor,
+            form,
+            raw_arguments: false,
+            workspace,
+            active_editor,
+            buffer,
+            command,
+            server_ids,
+            lsp_store,
+            reopen_selector,
+            inspect_result,
+            arguments_validator,
+            error: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn shown_form(&self) -> Option<&ArgumentForm> {
+        self.form.as_ref().filter(|_| !self.raw_arguments)
+    }
+
+    pub(crate) fn update_form(
+        &mut self,
+        cx: &mut Context<Self>,
+        update: impl FnOnce(&mut ArgumentForm),
+    ) {
+        if let Some(form) = &mut self.form {
+            update(form);
+            self.error = None;
+            cx.notify();
+        }
+    }
+
+    /// Switches between the command's form and entering its arguments as raw JSON, which starts
+    /// out with the arguments entered in the form.
+    fn toggle_raw_arguments(
+        &mut self,
+        _: &ToggleRawArguments,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(form) = &self.form else {
+            return;
+        };
+        if !self.raw_arguments
+            && let Some(input) = form.input(cx).log_err()
+        {
+            self.arguments_editor.update(cx, |editor, cx| {
+                editor.set_text(input, window, cx);
+            });
+        }
+        self.raw_arguments = !self.raw_arguments;
+        self.error = None;
+        window.focus(&self.focus_handle(cx));
+        cx.notify();
+    }
+
+    fn on_arguments_editor_event(
+        &mut self,
+        _: &Entity<Editor>,
+        event: &EditorEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            EditorEvent::BufferEdited => {
+                if self.error.take().is_some() {
+                    cx.notify();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        self.dismiss(window, cx);
+    }
+
+    /// Dismisses the prompt, returning focus to the editor that the selector was opened from
+    /// rather than to the selector that the prompt replaced.
+    fn dismiss(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        window.focus(&self.active_editor.focus_handle(cx));
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let input = match self.shown_form().map(|form| form.input(cx)) {
+            Some(Ok(input)) => input,
+            Some(Err(error)) => {
+                self.error = Some(format!("Invalid arguments: {error}").into());
+                cx.notify();
+                return;
+            }
+            None => self.arguments_editor.read(cx).text(cx),
+        };
+        let Some(path) = arguments_file(&input) else {
+            let arguments_input = input.clone();
+            self.run(input, &arguments_input, window, cx);
+            return;
+        };
+        let load = load_arguments_file(&self.workspace, &self.buffer, path, cx);
+        cx.spawn_in(window, async move |this, cx| {
+            let contents = load.await;
+            this.update_in(cx, |this, window, cx| match contents {
+                Ok(contents) => this.run(input, &contents, window, cx),
+                Err(error) => {
+                    this.error = Some(format!("{error:#}").into());
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Runs the command with the arguments of `arguments_input`, which is the `input` entered
+    /// unless that names a file to load them from.
+    fn run(
+        &mut self,
+        input: String,
+        arguments_input: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let open_files = self
+            .workspace
+            .upgrade()
+            .map(|workspace| open_file_uris(workspace.read(cx), cx))
+            .unwrap_or_default();
+        let arguments = resolve_command_arguments(
+            arguments_input,
+            &self.command,
+            &self.buffer,
+            &open_files,
+            &self.active_editor,
+            cx,
+        )
+        .and_then(|arguments| {
+            if let Some(validator) = &self.arguments_validator {
+                validate_arguments(validator, &arguments)?;
+            }
+            Ok(arguments)
+        });
+        match arguments {
+            Ok(arguments) if self.inspect_result => {
+                if let Some(&server_id) = self.server_ids.first() {
+                    inspect_command_result(
+                        self.workspace.clone(),
+                        &self.lsp_store,
+                        server_id,
+                        self.command.clone(),
+                        arguments,
+                        window,
+                        cx,
+                    );
+                }
+                self.dismiss(window, cx);
+            }
+            Ok(arguments) => {
+                // Keep the unresolved input, so that templates are resolved against the
+                // editor state of the next run rather than replaying stale values.
+                cx.default_global::<LastCommandArguments>()
+                    .0
+                    .insert(self.command.clone(), input);
+                cx.set_global(LastRunCommand {
+                    command: self.command.clone(),
+                    server_ids: self.server_ids.clone(),
+                    input: arguments_input.to_string(),
+                });
+                confirm_and_run_command(
+                    self.workspace.clone(),
+                    &self.active_editor,
+                    &self.lsp_store,
+                    self.server_ids.clone(),
+                    self.command.clone(),
+                    arguments,
+                    window,
+                    cx,
+                );
+                self.dismiss(window, cx);
+                if self.reopen_selector {
+                    let workspace = self.workspace.clone();
+                    window.defer(cx, move |window, cx| {
+                        workspace
+                            .update(cx, |workspace, cx| {
+                                LspWorkspaceCommandSelector::toggle(workspace, window, cx);
+                            })
+                            .log_err();
+                    });
+                }
+            }
+            Err(error) => {
+                self.error = Some(format!("Invalid arguments: {error}").into());
+                cx.notify();
+            }
+        }
+    }
+}
+
+/// The path of the file that the arguments are loaded from, when they're entered as `@path`.
+fn arguments_file(input: &str) -> Option<&str> {
+    let path = input.trim().strip_prefix('@')?.trim();
+    (!path.is_empty()).then_some(path)
+}
+
+/// Reads the arguments file at `path`, relative to the root of the worktree of the buffer that
+/// the command runs against. It's opened through the project, so that it's also found in remote
+/// projects.
+fn load_arguments_file(
+    workspace: &WeakEntity<Workspace>,
+    buffer: &Entity<Buffer>,
+    path: &str,
+    cx: &mut App,
+) -> Task<Result<String>> {
+    let display_path = path.to_string();
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Task::ready(Err(anyhow!(
+            "{display_path} must be relative to the worktree root"
+        )));
+    }
+    let Some(worktree_id) = buffer.read(cx).file().map(|file| file.worktree_id(cx)) else {
+        return Task::ready(Err(anyhow!(
+            "Couldn't read {display_path}: the buffer isn't in a worktree"
+        )));
+    };
+    let project_path = ProjectPath {
+        worktree_id,
+        path: Arc::from(path),
+    };
+    let open = workspace.update(cx, |workspace, cx| {
+        workspace
+            .project()
+            .update(cx, |project, cx| project.open_buffer(project_path, cx))
+    });
+    cx.spawn(async move |cx| {
+        let buffer = open?
+            .await
+            .with_context(|| format!("Couldn't read {display_path}"))?;
+        buffer.read_with(cx, |buffer, _| buffer.text())
+    })
+}
+
+/// The JSON schema of `command`'s arguments array, which a server may advertise under
+/// `commandArgumentSchemas` in its experimental capabilities, keyed by command id. Only local
+/// servers' capabilities are known.
+fn arguments_schema(
+    lsp_store: &LspStore,
+    server_ids: &[LanguageServerId],
+    command: &str,
+) -> Option<Value> {
+    server_ids.iter().find_map(|server_id| {
+        let experimental = lsp_store
+            .language_server_for_id(*server_id)?
+            .capabilities()
+            .experimental?;
+        experimental
+            .get("commandArgumentSchemas")?
+            .get(command)
+            .cloned()
+    })
+}
+
+/// Checks the arguments against the schema, listing where each of them fails to match it.
+fn validate_arguments(validator: &jsonschema::Validator, arguments: &[Value]) -> Result<()> {
+    let arguments = Value::Array(arguments.to_vec());
+    let errors = validator
+        .iter_errors(&arguments)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            if path.is_empty() {
+                error.to_string()
+            } else {
+                format!("{path}: {error}")
+            }
+        })
+        .collect::<Vec<_>>();
+    anyhow::ensure!(errors.is_empty(), "{}", errors.join("; "));
+    Ok(())
+}
+
+/// Resolves the arguments entered for `command` against the buffer it runs against, the files
+/// open in the workspace, and the cursor and selection of `editor`.
+pub(crate) fn resolve_command_arguments(
+    input: &str,
+    command: &str,
+    buffer: &Entity<Buffer>,
+    open_files: &[String],
+    editor: &Entity<Editor>,
+    cx: &mut App,
+) -> Result<Vec<Value>> {
+    let uri = buffer_uri(buffer, cx);
+    let mut variables = template_variables(uri.as_ref(), open_files, editor, cx);
+    if let Some(folder) = workspace_folder_uri(buffer, cx) {
+        variables.insert("workspaceFolder", folder.to_string());
+    }
+    if !variables.contains("symbol") && input.contains("${symbol}") {
+        variables.insert("symbol", "");
+        warn_empty_template(
+            editor,
+            "The cursor isn't on an identifier, so ${symbol} was left empty",
+            cx,
+        );
+    }
+    if input.contains("${gitBranch}") {
+        match git_branch(buffer, editor, cx) {
+            Some(branch) => variables.insert("gitBranch", branch),
+            None => {
+                variables.insert("gitBranch", "");
+                warn_empty_template(
+                    editor,
+                    "The file isn't in a git repository with a branch checked out, so ${gitBranch} was left empty",
+                    cx,
+                );
+            }
+        }
+    }
+    let mut arguments = resolve_arguments(input, &variables)?;
+    if LspWorkspaceCommandSettings::get_global(cx)
+        .document_uri_commands
+        .iter()
+        .any(|uri_command| uri_command == command)
+        && let Some(uri) = uri
+    {
+        arguments.insert(0, json!({ "uri": uri }));
+    }
+    Ok(arguments)
+}
+
+/// The URI of the buffer's file, built from the worktree's path so that the files of remote
+/// projects have one too, as it's the host's servers that receive it.
+fn buffer_uri(buffer: &Entity<Buffer>, cx: &App) -> Option<lsp::Url> {
+    let file = File::from_dyn(buffer.read(cx).file())?;
+    let worktree_path = file.worktree.read(cx).abs_path();
+    let abs_path = if file.path.as_ref() == Path::new("") {
+        worktree_path.to_path_buf()
+    } else {
+        worktree_path.join(&file.path)
+    };
+    file_path_to_lsp_url(&abs_path).ok()
+}
+
+/// The URI of the root folder of the worktree that contains the buffer, which there isn't for a
+/// file that was opened on its own.
+fn workspace_folder_uri(buffer: &Entity<Buffer>, cx: &App) -> Option<lsp::Url> {
+    let file = File::from_dyn(buffer.read(cx).file())?;
+    let root_dir = file.worktree.read(cx).root_dir()?;
+    file_path_to_lsp_url(&root_dir).ok()
+}
+
+fn template_variables(
+    uri: Option<&lsp::Url>,
+    open_files: &[String],
+    editor: &Entity<Editor>,
+    cx: &mut App,
+) -> TemplateVariables {
+    let mut variables = TemplateVariables::default();
+    if let Some(uri) = uri {
+        variables.insert("file", uri.to_string());
+    }
+    variables.insert("openFiles", open_files.to_vec());
+
+    editor.update(cx, |editor, cx| {
+        let selection = editor.selections.newest::<usize>(cx);
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        if let Some((buffer_snapshot, offset)) = snapshot.point_to_buffer_offset(selection.head()) {
+            let position = buffer_snapshot.offset_to_point_utf16(offset);
+            variables.insert("line", position.row);
+            variables.insert("column", position.column);
+            if let Some(symbol) = symbol_at(buffer_snapshot, offset) {
+                variables.insert("symbol", symbol);
+            }
+            if let Some(diagnostic) = diagnostic_at(buffer_snapshot, offset) {
+                variables.insert("diagnostic", diagnostic);
+            }
+        }
+        variables.insert(
+            "selection",
+            snapshot
+                .text_for_range(selection.range())
+                .collect::<String>(),
+        );
+    });
+    variables
+}
+
+/// The URIs of the files open in the workspace's editors, on the host in remote projects,
+/// leaving out the buffers that aren't saved to a file. They're collected before resolving
+/// arguments, which may happen while the workspace is being updated.
+pub(crate) fn open_file_uris(workspace: &Workspace, cx: &App) -> Vec<String> {
+    let mut seen = HashSet::default();
+    workspace
+        .items_of_type::<Editor>(cx)
+        .flat_map(|editor| editor.read(cx).buffer().read(cx).all_buffers())
+        .filter(|buffer| seen.insert(buffer.read(cx).remote_id()))
+        .filter_map(|buffer| buffer_uri(&buffer, cx))
+        .map(|uri| uri.to_string())
+        .collect()
+}
+
+/// The identifier that the syntax tree has at `offset`, or just before it, so that a cursor at the
+/// end of an identifier is still on it.
+fn symbol_at(buffer: &BufferSnapshot, offset: usize) -> Option<String> {
+    [offset, offset.saturating_sub(1)]
+        .into_iter()
+        .find_map(|offset| {
+            let layer = buffer.syntax_layer_at(offset)?;
+            let node = layer.node().descendant_for_byte_range(offset, offset)?;
+            node.kind()
+                .contains("identifier")
+                .then(|| node.byte_range())
+        })
+        .map(|range| buffer.text_for_range(range).collect())
+}
+
+/// The most severe of the diagnostics at `offset`, in the form the language servers report them.
+fn diagnostic_at(buffer: &BufferSnapshot, offset: usize) -> Option<Value> {
+    let entry = buffer
+        .diagnostics_in_range::<_, PointUtf16>(offset..offset, false)
+        .min_by_key(|entry| entry.diagnostic.severity)?;
+    let diagnostic = entry.diagnostic;
+    let diagnostic = lsp::Diagnostic {
+        range: range_to_lsp(entry.range).log_err()?,
+        severity: Some(diagnostic.severity),
+        code: diagnostic.code,
+        code_description: diagnostic
+            .code_description
+            .map(|href| lsp::CodeDescription { href: Some(href) }),
+        source: diagnostic.source,
+        message: diagnostic.message,
+        related_information: None,
+        tags: diagnostic
+            .is_unnecessary
+            .then(|| vec![lsp::DiagnosticTag::UNNECESSARY]),
+        data: diagnostic.data,
+    };
+    serde_json::to_value(diagnostic).log_err()
+}
+
+/// The name of the branch checked out in the git repository that contains the buffer.
+fn git_branch(buffer: &Entity<Buffer>, editor: &Entity<Editor>, cx: &App) -> Option<String> {
+    let project = editor.read(cx).project.as_ref()?;
+    let (repository, _) = project
+        .read(cx)
+        .git_store()
+        .read(cx)
+        .repository_and_path_for_buffer_id(buffer.read(cx).remote_id(), cx)?;
+    let branch = repository.read(cx).branch.as_ref()?.name().to_string();
+    Some(branch)
+}
+
+/// Warns that a template was resolved to an empty string. The toast is deferred, as the
+/// arguments may be resolved while the workspace is being updated.
+fn warn_empty_template(editor: &Entity<Editor>, message: &'static str, cx: &mut App) {
+    struct EmptyTemplateToast;
+
+    let Some(workspace) = editor.read(cx).workspace() else {
+        return;
+    };
+    cx.defer(move |cx| {
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_toast(
+                Toast::new(NotificationId::unique::<EmptyTemplateToast>(), message).autohide(),
+                cx,
+            );
+        });
+    });
+}
+
+impl Render for CommandArgumentsPrompt {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let shows_form = self.shown_form().is_some();
+        let help_label = match &self.error {
+            Some(error) => Label::new(error.clone()).color(Color::Error),
+            None if shows_form => {
+                Label::new(format!("Arguments for {}", self.command)).color(Color::Muted)
+            }
+            None => Label::new(format!(
+                "Arguments for {} as a JSON array, or @path of a file with them, which may use ${{file}}, ${{line}}, ${{column}}, ${{selection}}, ${{symbol}}, ${{diagnostic}}, ${{openFiles}}, ${{workspaceFolder}} and ${{gitBranch}}",
+                self.command
+            ))
+            .color(Color::Muted),
+        };
+
+        let inputs = match self.shown_form() {
+            Some(form) => form.render(cx).into_any_element(),
+            None => self.arguments_editor.clone().into_any_element(),
+        };
+
+        let width = LspWorkspaceCommandSettings::get_global(cx).modal_width();
+        v_flex()
+            .w(rems(width))
+            .elevation_2(cx)
+            .key_context("CommandArgumentsPrompt")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::toggle_raw_arguments))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(inputs),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .justify_between()
+                    .child(help_label)
+                    .when(self.form.is_some(), |this| {
+                        let label = if shows_form { "Raw JSON" } else { "Form" };
+                        this.child(
+                            Button::new("toggle-raw-arguments", label)
+                                .label_size(LabelSize::Small)
+                                .key_binding(
+                                    KeyBinding::for_action(&ToggleRawArguments, window, cx)
+                                        .map(|binding| binding.size(rems_from_px(12.))),
+                                )
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(ToggleRawArguments.boxed_clone(), cx)
+                                }),
+                        )
+                    }),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arguments_file() {
+        assert_eq!(
+            arguments_file(" @fixtures/args.json "),
+            Some("fixtures/args.json")
+        );
+        assert_eq!(arguments_file("@"), None);
+        assert_eq!(arguments_file(r#"["@file"]"#), None);
+    }
+
+    #[test]
+    fn test_validate_arguments() {
+        let validator = jsonschema::validator_for(&json!({
+            "type": "array",
+            "prefixItems": [
+                {
+                    "type": "object",
+                    "properties": { "uri": { "type": "string" } },
+                    "required": ["uri"],
+                },
+                { "type": "integer" },
+            ],
+        }))
+        .unwrap();
+        assert!(
+            validate_arguments(&validator, &[json!({ "uri": "file:///a.rs" }), json!(3)]).is_ok()
+        );
+        assert_eq!(
+            validate_arguments(&validator, &[json!({ "uri": 1 }), json!("3")])
+                .unwrap_err()
+                .to_string(),
+            "/0/uri: 1 is not of type \"string\"; /1: \"3\" is not of type \"integer\""
+        );
+    }
+}
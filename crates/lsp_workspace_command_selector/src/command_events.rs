@@ -0,0 +1,39 @@
+use gpui::{App, AppContext as _, Context, Entity, EventEmitter, Global, SharedString};
+use lsp::LanguageServerId;
+
+pub(crate) fn init(cx: &mut App) {
+    let command_events = cx.new(|_| CommandEvents);
+    cx.set_global(GlobalCommandEvents(command_events));
+}
+
+struct GlobalCommandEvents(Entity<CommandEvents>);
+
+impl Global for GlobalCommandEvents {}
+
+/// An event bus for broadcasting the outcome of the LSP workspace commands run from the selector.
+pub struct CommandEvents;
+
+impl CommandEvents {
+    /// Returns the global [`CommandEvents`].
+    pub fn try_global(cx: &App) -> Option<Entity<Self>> {
+        cx.try_global::<GlobalCommandEvents>()
+            .map(|global| global.0.clone())
+    }
+
+    pub fn emit(&mut self, event: CommandEvent, cx: &mut Context<Self>) {
+        cx.emit(event)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum CommandEvent {
+    /// A command finished on one of the servers it ran on, with the number of files it edited
+    /// or the reason it failed.
+    CommandExecuted {
+        command: String,
+        server_id: LanguageServerId,
+        result: Result<usize, SharedString>,
+    },
+}
+
+impl EventEmitter<CommandEvent> for CommandEvents {}
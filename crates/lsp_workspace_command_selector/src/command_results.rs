@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use gpui::{App, AppContext as _, Context, SharedString};
+use ui::prelude::*;
+use workspace::{
+    Workspace,
+    notifications::{NotificationId, simple_message_notification::MessageNotification},
+};
+
+/// The outcome of running a command on one server: the number of files it edited, or why it
+/// failed.
+#[derive(Clone, Debug)]
+pub(crate) struct CommandResult {
+    pub(crate) command: String,
+    /// The server the command ran on, unless it failed before reaching one.
+    pub(crate) server_name: Option<SharedString>,
+    pub(crate) result: Result<usize, SharedString>,
+}
+
+impl CommandResult {
+    fn line(&self) -> String {
+        let command = match &self.server_name {
+            Some(server_name) => format!("{} on {server_name}", self.command),
+            None => self.command.clone(),
+        };
+        match &self.result {
+            Ok(0) => format!("{command}: no edits"),
+            Ok(1) => format!("{command}: edited 1 file"),
+            Ok(edited_files) => format!("{command}: edited {edited_files} files"),
+            Err(error) => format!("{command}: {error}"),
+        }
+    }
+}
+
+/// Shows a notification listing which of a batch of commands succeeded and which failed, with
+/// their errors, under a summary of the batch. With `retry`, it offers to run the failed
+/// commands again.
+pub(crate) fn show_command_results(
+    workspace: &mut Workspace,
+    summary: String,
+    results: Vec<CommandResult>,
+    retry: Option<Arc<dyn Fn(&mut App)>>,
+    cx: &mut Context<Workspace>,
+) {
+    struct CommandResultsNotification;
+
+    let lines = results
+        .iter()
+        .map(|result| (result.result.is_ok(), SharedString::from(result.line())))
+        .collect::<Vec<_>>();
+    workspace.show_notification(
+        NotificationId::unique::<CommandResultsNotification>(),
+        cx,
+        move |cx| {
+            cx.new(|cx| {
+                let notification = MessageNotification::new_from_builder(cx, move |_, _| {
+                    v_flex()
+                        .gap_0p5()
+                        .children(lines.iter().map(|(succeeded, line)| {
+                            let (icon, color) = if *succeeded {
+                                (IconName::Check, Color::Success)
+                            } else {
+                                (IconName::XCircle, Color::Error)
+                            };
+                            h_flex()
+                                .gap_1()
+                                .items_start()
+                                .child(Icon::new(icon).size(IconSize::Small).color(color))
+                                .child(Label::new(line.clone()).size(LabelSize::Small))
+                        }))
+                        .into_any_element()
+                })
+                .with_title(summary.clone());
+                match retry.clone() {
+                    Some(retry) => notification
+                        .primary_message("Retry")
+                        .primary_icon(IconName::RotateCw)
+                        .primary_on_click(move |_, cx| retry(cx)),
+                    None => notification,
+                }
+            })
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_result_line() {
+        let result = |server_name: Option<&str>, result| CommandResult {
+            command: "eslint.applyAllFixes".to_string(),
+            server_name: server_name.map(SharedString::from),
+            result,
+        };
+        assert_eq!(
+            result(Some("eslint"), Ok(0)).line(),
+            "eslint.applyAllFixes on eslint: no edits"
+        );
+        assert_eq!(
+            result(Some("eslint"), Ok(2)).line(),
+            "eslint.applyAllFixes on eslint: edited 2 files"
+        );
+        assert_eq!(
+            result(Some("eslint"), Err("timed out".into())).line(),
+            "eslint.applyAllFixes on eslint: timed out"
+        );
+        assert_eq!(
+            result(None, Err("invalid arguments".into())).line(),
+            "eslint.applyAllFixes: invalid arguments"
+        );
+    }
+}
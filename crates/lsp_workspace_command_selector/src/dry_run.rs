@@ -0,0 +1,114 @@
+use anyhow::Result;
+use gpui::{App, AppContext as _, Global, SharedString, WeakEntity};
+use lsp::LanguageServerId;
+use serde_json::Value;
+use ui::prelude::*;
+use util::ResultExt;
+use workspace::{
+    Toast, Workspace,
+    notifications::{NotificationId, simple_message_notification::MessageNotification},
+};
+
+use crate::LOG_TARGET;
+
+/// Set while commands are only logged and shown, with the parameters they'd be executed with,
+/// rather than executed. It isn't persisted, so that it can't be left on by mistake.
+struct DryRun;
+
+impl Global for DryRun {}
+
+pub(crate) fn is_dry_run(cx: &App) -> bool {
+    cx.has_global::<DryRun>()
+}
+
+pub(crate) fn toggle_dry_run(workspace: &mut Workspace, cx: &mut Context<Workspace>) {
+    struct DryRunToast;
+
+    let message = if is_dry_run(cx) {
+        cx.remove_global::<DryRun>();
+        "Dry run off: LSP workspace commands are executed again"
+    } else {
+        cx.set_global(DryRun);
+        "Dry run on: LSP workspace commands are shown rather than executed"
+    };
+    workspace.show_toast(
+        Toast::new(NotificationId::unique::<DryRunToast>(), message).autohide(),
+        cx,
+    );
+}
+
+/// A command that a dry run didn't execute, with the arguments it would have been sent, or why
+/// they couldn't be resolved.
+pub(crate) struct DryRunCommand {
+    pub(crate) command: String,
+    pub(crate) server_ids: Vec<LanguageServerId>,
+    pub(crate) arguments: Result<Vec<Value>>,
+}
+
+/// Logs the parameters that the commands would have been executed with and shows them in a
+/// notification. It's deferred, as the commands may run while the workspace is being updated.
+pub(crate) fn show_dry_run(
+    workspace: WeakEntity<Workspace>,
+    commands: Vec<DryRunCommand>,
+    cx: &mut App,
+) {
+    struct DryRunNotification;
+
+    let lines = commands
+        .into_iter()
+        .map(|dry_run| {
+            let params = dry_run.arguments.map(|arguments| {
+                let params = lsp::ExecuteCommandParams {
+                    command: dry_run.command.clone(),
+                    arguments,
+                    work_done_progress_params: Default::default(),
+                };
+                serde_json::to_string_pretty(&params).unwrap_or_default()
+            });
+            match params {
+                Ok(params) => {
+                    log::info!(
+                        target: LOG_TARGET,
+                        "dry run of {} on {:?}, not executed: {params}",
+                        dry_run.command,
+                        dry_run.server_ids,
+                    );
+                    SharedString::from(params)
+                }
+                Err(error) => {
+                    log::info!(
+                        target: LOG_TARGET,
+                        "dry run of {}, with invalid arguments: {error:#}",
+                        dry_run.command,
+                    );
+                    format!("{}: invalid arguments: {error:#}", dry_run.command).into()
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    cx.defer(move |cx| {
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_notification(
+                    NotificationId::unique::<DryRunNotification>(),
+                    cx,
+                    move |cx| {
+                        cx.new(|cx| {
+                            MessageNotification::new_from_builder(cx, move |_, cx| {
+                                v_flex()
+                                    .gap_1()
+                                    .children(lines.iter().map(|line| {
+                                        Label::new(line.clone())
+                                            .size(LabelSize::Small)
+                                            .buffer_font(cx)
+                                    }))
+                                    .into_any_element()
+                            })
+                            .with_title("Dry run: nothing was executed")
+                        })
+                    },
+                );
+            })
+            .log_err();
+    });
+}
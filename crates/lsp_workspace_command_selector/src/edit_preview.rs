@@ -0,0 +1,319 @@
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{Context as _, Result, anyhow};
+use buffer_diff::BufferDiff;
+use collections::HashMap;
+use editor::{DEFAULT_MULTIBUFFER_CONTEXT, Editor, MultiBuffer, PathKey};
+use futures::{channel::oneshot, future};
+use gpui::{App, AppContext as _, Entity, PromptLevel, Task, WeakEntity, Window};
+use language::{Anchor, Bias, Buffer, Capability, Point, ToPoint as _, point_from_lsp};
+use project::{Project, ProjectTransaction};
+use workspace::{SaveIntent, Workspace};
+
+/// The edits of a command to one of the files, anchored in its buffer so that they're still
+/// applied where they were meant to be when the buffer is edited while they're previewed.
+struct BufferEdits {
+    buffer: Entity<Buffer>,
+    path: String,
+    edits: Vec<(Range<Anchor>, Arc<str>)>,
+}
+
+/// Shows the edits that commands returned as diffs in a read-only multibuffer, and applies them
+/// only once they're confirmed. Resolves to the transaction of the applied edits, or to none
+/// when they're cancelled, such as by dismissing the prompt.
+pub(crate) fn preview_workspace_edits(
+    workspace: WeakEntity<Workspace>,
+    project: Entity<Project>,
+    command: String,
+    workspace_edits: Vec<lsp::WorkspaceEdit>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<Result<Option<ProjectTransaction>>> {
+    let mut text_edits = HashMap::<lsp::Url, Vec<lsp::TextEdit>>::default();
+    for workspace_edit in workspace_edits {
+        match text_edits_by_uri(workspace_edit) {
+            Ok(edits) => {
+                for (uri, edits) in edits {
+                    text_edits.entry(uri).or_default().extend(edits);
+                }
+            }
+            Err(error) => return Task::ready(Err(error)),
+        }
+    }
+    let opens = text_edits
+        .into_iter()
+        .map(|(uri, edits)| {
+            let open = uri
+                .to_file_path()
+                .map_err(|()| anyhow!("invalid file URI {uri}"))
+                .and_then(|abs_path| {
+                    project
+                        .read(cx)
+                        .find_project_path(&abs_path, cx)
+                        .with_context(|| format!("{} isn't in the project", abs_path.display()))
+                })
+                .map(|project_path| {
+                    project.update(cx, |project, cx| project.open_buffer(project_path, cx))
+                });
+            async move { anyhow::Ok((open?.await?, edits)) }
+        })
+        .collect::<Vec<_>>();
+    window.spawn(cx, async move |cx| {
+        let opened = future::try_join_all(opens).await?;
+        let mut buffer_edits = cx.update(|_, cx| {
+            opened
+                .into_iter()
+                .map(|(buffer, edits)| anchor_edits(buffer, edits, cx))
+                .collect::<Vec<_>>()
+        })?;
+        buffer_edits.retain(|buffer_edits| !buffer_edits.edits.is_empty());
+        if buffer_edits.is_empty() {
+            return Ok(None);
+        }
+        buffer_edits.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let (preview, diffs_computed) =
+            cx.update(|window, cx| open_preview(&project, &command, &buffer_edits, window, cx))?;
+        future::join_all(diffs_computed).await;
+        let preview_id = preview.entity_id();
+        workspace.update_in(cx, |workspace, window, cx| {
+            workspace.add_item_to_active_pane(Box::new(preview), None, true, window, cx);
+        })?;
+        let summary = preview_summary(
+            buffer_edits
+                .iter()
+                .map(|buffer_edits| (buffer_edits.path.as_str(), buffer_edits.edits.len())),
+        );
+        let answer = cx.update(|window, cx| {
+            window.prompt(
+                PromptLevel::Info,
+                &format!("Apply the edits of {command}?"),
+                Some(&summary),
+                &["Apply", "Cancel"],
+                cx,
+            )
+        })?;
+        let apply = answer.await == Ok(0);
+        workspace.update_in(cx, |workspace, window, cx| {
+            workspace.active_pane().update(cx, |pane, cx| {
+                pane.close_item_by_id(preview_id, SaveIntent::Skip, window, cx)
+                    .detach_and_log_err(cx);
+            });
+        })?;
+        if !apply {
+            return Ok(None);
+        }
+
+        let mut transaction = ProjectTransaction::default();
+        cx.update(|_, cx| {
+            for buffer_edits in buffer_edits {
+                let applied = buffer_edits.buffer.update(cx, |buffer, cx| {
+                    buffer.finalize_last_transaction();
+                    buffer.start_transaction();
+                    buffer.edit(buffer_edits.edits, None, cx);
+                    buffer.end_transaction(cx)?;
+                    buffer.finalize_last_transaction().cloned()
+                });
+                if let Some(applied) = applied {
+                    transaction.0.insert(buffer_edits.buffer, applied);
+                }
+            }
+        })?;
+        Ok(Some(transaction))
+    })
+}
+
+/// How many files a `WorkspaceEdit` edits, creates, renames or deletes.
+pub(crate) fn edited_file_count(edit: &lsp::WorkspaceEdit) -> usize {
+    match &edit.document_changes {
+        Some(lsp::DocumentChanges::Edits(edits)) => edits.len(),
+        Some(lsp::DocumentChanges::Operations(operations)) => operations.len(),
+        None => edit.changes.as_ref().map_or(0, |changes| changes.len()),
+    }
+}
+
+/// The text edits of a `WorkspaceEdit` by the URI of the file they're made to. Edits that
+/// create, rename or delete files aren't supported, as they can't be shown as diffs.
+fn text_edits_by_uri(
+    workspace_edit: lsp::WorkspaceEdit,
+) -> Result<Vec<(lsp::Url, Vec<lsp::TextEdit>)>> {
+    let mut text_edits = Vec::new();
+    if let Some(document_changes) = workspace_edit.document_changes {
+        let document_edits = match document_changes {
+            lsp::DocumentChanges::Edits(edits) => edits,
+            lsp::DocumentChanges::Operations(operations) => operations
+                .into_iter()
+                .map(|operation| match operation {
+                    lsp::DocumentChangeOperation::Edit(edit) => Ok(edit),
+                    lsp::DocumentChangeOperation::Op(_) => Err(anyhow!(
+                        "the edits create, rename or delete files, which can't be previewed. \
+                        Turn off `preview_edits` to apply them"
+                    )),
+                })
+                .collect::<Result<_>>()?,
+        };
+        for document_edit in document_edits {
+            let edits = document_edit
+                .edits
+                .into_iter()
+                .map(|edit| match edit {
+                    lsp::OneOf::Left(edit) => edit,
+                    lsp::OneOf::Right(edit) => edit.text_edit,
+                })
+                .collect();
+            text_edits.push((document_edit.text_document.uri, edits));
+        }
+    } else if let Some(changes) = workspace_edit.changes {
+        text_edits.extend(changes);
+    }
+    Ok(text_edits)
+}
+
+fn anchor_edits(buffer: Entity<Buffer>, edits: Vec<lsp::TextEdit>, cx: &App) -> BufferEdits {
+    let path = match buffer.read(cx).file() {
+        Some(file) => file.path().to_string_lossy().into_owned(),
+        None => "untitled".to_string(),
+    };
+    let snapshot = buffer.read(cx).snapshot();
+    let edits = edits
+        .into_iter()
+        .map(|edit| {
+            let start = snapshot.clip_point_utf16(point_from_lsp(edit.range.start), Bias::Left);
+            let end = snapshot.clip_point_utf16(point_from_lsp(edit.range.end), Bias::Left);
+            (
+                snapshot.anchor_before(start)..snapshot.anchor_after(end),
+                Arc::from(edit.new_text),
+            )
+        })
+        .collect();
+    BufferEdits {
+        buffer,
+        path,
+        edits,
+    }
+}
+
+/// Makes the edits to branches of the buffers, and opens the branches in a read-only editor that
+/// shows how they differ from the buffers. Returns the editor along with the diffs being
+/// computed.
+fn open_preview(
+    project: &Entity<Project>,
+    command: &str,
+    buffer_edits: &[BufferEdits],
+    window: &mut Window,
+    cx: &mut App,
+) -> (Entity<Editor>, Vec<oneshot::Receiver<()>>) {
+    let mut diffs_computed = Vec::new();
+    let multi_buffer = cx.new(|cx| {
+        let mut multi_buffer =
+            MultiBuffer::new(Capability::ReadOnly).with_title(format!("Edits of {command}"));
+        for buffer_edits in buffer_edits {
+            let branch = buffer_edits
+                .buffer
+                .update(cx, |buffer, cx| buffer.branch(cx));
+            branch.update(cx, |branch, cx| {
+                branch.edit(buffer_edits.edits.iter().cloned(), None, cx);
+            });
+            let branch_snapshot = branch.read(cx).snapshot();
+            let edited_ranges = buffer_edits
+                .edits
+                .iter()
+                .map(|(range, _)| {
+                    range.start.to_point(&branch_snapshot)..range.end.to_point(&branch_snapshot)
+                })
+                .collect::<Vec<Range<Point>>>();
+            multi_buffer.set_excerpts_for_path(
+                PathKey::for_buffer(&branch, cx),
+                branch.clone(),
+                edited_ranges,
+                DEFAULT_MULTIBUFFER_CONTEXT,
+                cx,
+            );
+            let diff = cx.new(|cx| {
+                let mut diff = BufferDiff::new(&branch.read(cx).text_snapshot(), cx);
+                diffs_computed.push(diff.set_base_text_buffer(
+                    buffer_edits.buffer.clone(),
+                    branch.read(cx).text_snapshot(),
+                    cx,
+                ));
+                diff
+            });
+            multi_buffer.add_diff(diff, cx);
+        }
+        multi_buffer
+    });
+    let editor = cx.new(|cx| {
+        let mut editor = Editor::for_multibuffer(multi_buffer, Some(project.clone()), window, cx);
+        editor.set_read_only(true);
+        editor.set_expand_all_diff_hunks(cx);
+        editor
+    });
+    (editor, diffs_computed)
+}
+
+/// The files that the edits are made to, with how many edits each, for confirming them.
+fn preview_summary<'a>(files: impl IntoIterator<Item = (&'a str, usize)>) -> String {
+    files
+        .into_iter()
+        .map(|(path, edit_count)| match edit_count {
+            1 => format!("{path}: 1 edit"),
+            edit_count => format!("{path}: {edit_count} edits"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_text_edits_by_uri() {
+        let edit = |new_text: &str| {
+            json!({
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 3 },
+                },
+                "newText": new_text,
+            })
+        };
+        let workspace_edit = serde_json::from_value::<lsp::WorkspaceEdit>(json!({
+            "documentChanges": [
+                {
+                    "textDocument": { "uri": "file:///a.rs", "version": 1 },
+                    "edits": [edit("foo"), edit("bar")],
+                },
+            ],
+        }))
+        .unwrap();
+        let text_edits = text_edits_by_uri(workspace_edit).unwrap();
+        assert_eq!(text_edits.len(), 1);
+        assert_eq!(text_edits[0].0.as_str(), "file:///a.rs");
+        assert_eq!(
+            text_edits[0]
+                .1
+                .iter()
+                .map(|edit| edit.new_text.as_str())
+                .collect::<Vec<_>>(),
+            ["foo", "bar"]
+        );
+
+        let workspace_edit = serde_json::from_value::<lsp::WorkspaceEdit>(json!({
+            "documentChanges": [
+                { "kind": "create", "uri": "file:///b.rs" },
+            ],
+        }))
+        .unwrap();
+        assert!(text_edits_by_uri(workspace_edit).is_err());
+    }
+
+    #[test]
+    fn test_preview_summary() {
+        assert_eq!(
+            preview_summary([("src/a.rs", 1), ("src/b.rs", 3)]),
+            "src/a.rs: 1 edit\nsrc/b.rs: 3 edits"
+        );
+    }
+}
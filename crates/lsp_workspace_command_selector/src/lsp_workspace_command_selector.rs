@@ -0,0 +1,4550 @@
+mod active_buffer_commands;
+mod argument_form;
+mod argument_templates;
+mod command_arguments_prompt;
+mod command_events;
+mod command_results;
+mod dry_run;
+mod edit_preview;
+mod lsp_workspace_command_settings;
+
+pub use active_buffer_commands::ActiveBufferCommands;
+use anyhow::{Context as _, anyhow};
+use collections::{HashMap, HashSet, IndexMap};
+use command_arguments_prompt::{
+    CommandArgumentsPrompt, last_command_arguments, open_file_uris, resolve_command_arguments,
+};
+pub use command_events::{CommandEvent, CommandEvents};
+use command_palette_hooks::{CommandPaletteCommand, CommandPaletteCommands};
+use command_results::{CommandResult, show_command_results};
+use db::kvp::KEY_VALUE_STORE;
+use dry_run::{DryRunCommand, is_dry_run, show_dry_run, toggle_dry_run};
+use edit_preview::{edited_file_count, preview_workspace_edits};
+use editor::{
+    Anchor, Bias, Editor, MultiBuffer, MultibufferSelectionMode, SelectionEffects,
+    scroll::Autoscroll,
+};
+use futures::{channel::oneshot, future};
+use fuzzy::{StringMatch, StringMatchCandidate, match_strings};
+use gpui::{
+    Action, Animation, AnimationExt, AnyWindowHandle, App, AsyncApp, ClipboardItem, Context,
+    DismissEvent, Entity, EntityId, EventEmitter, FocusHandle, Focusable, Global, ParentElement,
+    PromptLevel, Render, Styled, Subscription, Task, Transformation, WeakEntity, Window, actions,
+    percentage,
+};
+use language::{Buffer, BufferId, Language, LanguageName, point_from_lsp};
+use lsp::{LanguageServerId, LanguageServerName, LanguageServerSelector};
+pub use lsp_workspace_command_settings::LspWorkspaceCommandSettings;
+use lsp_workspace_command_settings::RestartStoppedServers;
+use picker::{Direction, Picker, PickerDelegate};
+use project::{
+    LanguageServerCommands, Location, LspStore, LspStoreEvent, ProjectTransaction,
+    lsp_store::{LanguageServerState, workspace_edit_from_response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use settings::Settings;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use ui::{HighlightedLabel, Indicator, KeyBinding, ListItem, ListItemSpacing, Tooltip, prelude::*};
+use util::{ResultExt, post_inc};
+use workspace::{
+    ModalView, Toast, Workspace, WorkspaceId,
+    notifications::{DetachAndPromptErr, NotificationId},
+};
+pub use zed_actions::lsp_workspace_command_selector::Toggle;
+
+actions!(
+    lsp_workspace_command_selector,
+    [
+        /// Runs the most recently run LSP workspace command again, with the same arguments.
+        RunLast,
+        /// Stars or unstars the selected command, keeping starred commands at the top.
+        ToggleFavorite,
+        /// Runs the selected command and opens the value it returns in a new buffer, rather
+        /// than applying it.
+        InspectResult,
+        /// Runs the selected command on every server that provides it, like secondary confirm.
+        RunOnAllServers,
+        /// Runs the selected command and reopens the selector afterwards, so that another one
+        /// can be run.
+        RunAndKeepOpen,
+        /// Forgets the commands discovered so far, so that they're listed anew by the servers.
+        /// In the selector, the commands are discovered again without closing it.
+        RefreshCommands,
+        /// Switches the order of the commands listed without a query between recently used
+        /// first, alphabetical and most used first.
+        CycleSortOrder,
+        /// Collapses or expands the commands of the selected server, remembering the choice
+        /// for the workspace.
+        ToggleServerGroup,
+        /// Switches between matching the query regardless of case and matching its uppercase
+        /// characters exactly.
+        ToggleCaseSensitive,
+        /// Forgets the recently and frequently run commands of the workspace and the starred
+        /// commands, listing the commands alphabetically again.
+        ClearHistory,
+        /// Adds the selected command to the queue of commands that confirming runs one after
+        /// another, or removes it from the queue.
+        ToggleQueued,
+        /// Opens a buffer with the workspace commands that each language server of the active
+        /// buffer advertises, including those left out of the selector by the settings.
+        DumpCapabilities,
+        /// Switches between running commands and only showing the parameters they'd be executed
+        /// with, to check how their arguments are resolved.
+        ToggleDryRun,
+        /// Switches the arguments prompt between the command's form and entering its arguments
+        /// as raw JSON.
+        ToggleRawArguments,
+        /// Copies the id of the selected command to the clipboard, without running it.
+        CopyCommandId,
+        /// Copies a keymap entry binding a key to run the selected command on its server.
+        CopyKeybinding
+    ]
+);
+
+/// Runs a workspace command of the active buffer's language servers without opening the selector.
+#[derive(PartialEq, Clone, Deserialize, JsonSchema, Action)]
+#[action(namespace = lsp_workspace_command_selector)]
+#[serde(deny_unknown_fields)]
+pub struct RunByName {
+    /// The id of the command, such as `rust-analyzer.reloadWorkspace`.
+    pub command: String,
+    /// The arguments to send with the command, which may use the same `${file}`, `${line}`,
+    /// `${column}`, `${selection}`, `${symbol}`, `${diagnostic}`, `${openFiles}`,
+    /// `${workspaceFolder}` and `${gitBranch}` templates as the arguments entered in the selector.
+    #[serde(default)]
+    pub arguments: Vec<Value>,
+    /// The name of the language server to run the command on, when several of them provide it.
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+/// Runs one of the `command_macros` of the settings, a named sequence of LSP workspace commands.
+#[derive(PartialEq, Clone, Deserialize, JsonSchema, Action)]
+#[action(namespace = lsp_workspace_command_selector)]
+#[serde(deny_unknown_fields)]
+pub struct RunMacro {
+    /// The name of the macro in the settings.
+    pub name: String,
+}
+
+const NO_COMMANDS_MESSAGE: &str =
+    "No workspace commands are available for this buffer's language servers.";
+const NO_ACTIVE_EDITOR_MESSAGE: &str = "Open a file to run LSP workspace commands";
+/// The log target of command executions, for following them in the log.
+const LOG_TARGET: &str = "lsp_workspace_command";
+/// Added to the score of a command whose alias is the query, which outweighs any match score
+/// and usage.
+const ALIAS_MATCH_BONUS: f64 = 100.;
+/// How long to wait for more typing before matching the query.
+const MATCH_DEBOUNCE: Duration = Duration::from_millis(50);
+
+pub fn init(cx: &mut App) {
+    LspWorkspaceCommandSettings::register(cx);
+    command_events::init(cx);
+    FavoriteCommands::load(cx);
+    CommandSortOrder::load(cx);
+    CaseSensitiveMatching::load(cx);
+    CollapsedServers::load(cx);
+    CommandPaletteCommands::register(cx, palette_commands);
+    cx.observe_new(LspWorkspaceCommandSelector::register)
+        .detach();
+}
+
+pub struct LspWorkspaceCommandSelector {
+    picker: Entity<Picker<LspWorkspaceCommandSelectorDelegate>>,
+    reload_commands: Task<()>,
+    _lsp_store_subscription: Subscription,
+}
+
+impl LspWorkspaceCommandSelector {
+    fn register(
+        workspace: &mut Workspace,
+        _window: Option<&mut Window>,
+        cx: &mut Context<Workspace>,
+    ) {
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        cx.subscribe(&lsp_store, |workspace, _, event, cx| match event {
+            LspStoreEvent::LanguageServerAdded(..) | LspStoreEvent::LanguageServerRemoved(_) => {
+                CommandCache::clear(cx);
+                LastCommandStatuses::clear(cx);
+                Self::cache_active_commands(workspace, cx);
+            }
+            LspStoreEvent::LanguageServerCapabilitiesUpdated(_) => {
+                CommandCache::clear(cx);
+                Self::cache_active_commands(workspace, cx);
+            }
+            _ => {}
+        })
+        .detach();
+        cx.subscribe_self::<workspace::Event>(|workspace, event, cx| {
+            if let workspace::Event::ActiveItemChanged = event {
+                Self::cache_active_commands(workspace, cx);
+            }
+        })
+        .detach();
+        workspace.set_lsp_command_provider(TaskCommandProvider);
+        workspace.register_action(move |workspace, _: &Toggle, window, cx| {
+            Self::toggle(workspace, window, cx);
+        });
+        workspace.register_action(move |workspace, _: &RunLast, window, cx| {
+            Self::run_last(workspace, window, cx);
+        });
+        workspace.register_action(move |workspace, action: &RunByName, window, cx| {
+            Self::run_by_name(
+                workspace,
+                action.command.clone(),
+                action.arguments.clone(),
+                action.server.clone(),
+                window,
+                cx,
+            );
+        });
+        workspace.register_action(move |workspace, action: &RunMacro, window, cx| {
+            Self::run_macro(workspace, action.name.clone(), window, cx);
+        });
+        workspace.register_action(move |workspace, _: &DumpCapabilities, window, cx| {
+            Self::dump_capabilities(workspace, window, cx);
+        });
+        workspace.register_action(move |_, _: &RefreshCommands, _, cx| {
+            CommandCache::clear(cx);
+        });
+        workspace.register_action(move |workspace, _: &ClearHistory, _, cx| {
+            Self::clear_history(workspace, cx);
+        });
+        workspace.register_action(move |workspace, _: &ToggleDryRun, _, cx| {
+            toggle_dry_run(workspace, cx);
+        });
+    }
+
+    /// Discovers the commands of the active editor's buffers ahead of time, so that they're
+    /// offered in the command palette.
+    fn cache_active_commands(workspace: &Workspace, cx: &mut App) {
+        let Some((editor, active_buffer)) = Self::active_editor_and_buffer(workspace, cx) else {
+            return;
+        };
+        let buffers = Self::editor_buffers(&editor, active_buffer, cx);
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        discover_commands(buffers, &lsp_store, cx).detach_and_log_err(cx);
+    }
+
+    /// Returns the active editor along with the buffer under its cursor. When the active item
+    /// isn't an editor, such as a terminal, the most recently active editor is used instead.
+    fn active_editor_and_buffer(
+        workspace: &Workspace,
+        cx: &App,
+    ) -> Option<(Entity<Editor>, Entity<Buffer>)> {
+        let editor = workspace
+            .active_item(cx)
+            .and_then(|item| item.act_as::<Editor>(cx))
+            .or_else(|| workspace.recent_active_item_by_type::<Editor>(cx))?;
+        let (_, buffer, _) = editor.read(cx).active_excerpt(cx)?;
+        Some((editor, buffer))
+    }
+
+    /// Returns the buffers of the editor's excerpts, starting with the buffer under the cursor, so
+    /// that its commands are preferred over the same commands of the other buffers.
+    fn editor_buffers(
+        editor: &Entity<Editor>,
+        active_buffer: Entity<Buffer>,
+        cx: &App,
+    ) -> Vec<Entity<Buffer>> {
+        let other_buffers = editor
+            .read(cx)
+            .buffer()
+            .read(cx)
+            .all_buffers()
+            .into_iter()
+            .filter(|buffer| *buffer != active_buffer);
+        let mut buffers = vec![active_buffer.clone()];
+        buffers.extend(other_buffers);
+        buffers
+    }
+
+    fn toggle(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        if let Some(selector) = workspace.active_modal::<Self>(cx) {
+            selector.update(cx, |_, cx| cx.emit(DismissEvent));
+            return;
+        }
+        let Some((editor, active_buffer)) = Self::active_editor_and_buffer(workspace, cx) else {
+            Self::show_unavailable_toast(workspace, NO_ACTIVE_EDITOR_MESSAGE, cx);
+            return;
+        };
+        let buffers = Self::editor_buffers(&editor, active_buffer, cx);
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        let load_history = CommandHistories::load(history_key(workspace, cx), cx);
+        let workspace_handle = workspace.weak_handle();
+        workspace.toggle_modal(window, cx, move |window, cx| {
+            LspWorkspaceCommandSelector::new(
+                workspace_handle,
+                editor,
+                buffers,
+                lsp_store,
+                load_history,
+                window,
+                cx,
+            )
+        });
+    }
+
+    fn run_last(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        let Some(last_run) = cx.try_global::<LastRunCommand>() else {
+            Self::show_unavailable_toast(
+                workspace,
+                "No LSP workspace command has been run yet",
+                cx,
+            );
+            return;
+        };
+        let command = last_run.command.clone();
+        let server_ids = last_run.server_ids.clone();
+        let input = last_run.input.clone();
+        let Some((editor, buffer)) = Self::active_editor_and_buffer(workspace, cx) else {
+            Self::show_unavailable_toast(workspace, NO_ACTIVE_EDITOR_MESSAGE, cx);
+            return;
+        };
+        let open_files = open_file_uris(workspace, cx);
+        match resolve_command_arguments(&input, &command, &buffer, &open_files, &editor, cx) {
+            Ok(arguments) => {
+                let lsp_store = workspace.project().read(cx).lsp_store();
+                confirm_and_run_command(
+                    workspace.weak_handle(),
+                    &editor,
+                    &lsp_store,
+                    server_ids,
+                    command,
+                    arguments,
+                    window,
+                    cx,
+                );
+            }
+            Err(error) => Self::show_unavailable_toast(
+                workspace,
+                format!("Invalid arguments for {command}: {error}"),
+                cx,
+            ),
+        }
+    }
+
+    fn run_by_name(
+        workspace: &mut Workspace,
+        command: String,
+        arguments: Vec<Value>,
+        server: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let input = if arguments.is_empty() {
+            String::new()
+        } else {
+            Value::from(arguments).to_string()
+        };
+        let Some((editor, active_buffer)) = Self::active_editor_and_buffer(workspace, cx) else {
+            Self::show_unavailable_toast(workspace, NO_ACTIVE_EDITOR_MESSAGE, cx);
+            return;
+        };
+        let buffers = Self::editor_buffers(&editor, active_buffer, cx);
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        let discovery = discover_commands(buffers, &lsp_store, cx);
+        cx.spawn_in(window, async move |workspace, cx| {
+            let (commands, server_names) = discovery.await?;
+            let choice = workspace.update_in(cx, |workspace, window, cx| {
+                let mut matching_commands = commands
+                    .iter()
+                    .filter(|workspace_command| {
+                        workspace_command.command == command
+                            && server
+                                .as_ref()
+                                .is_none_or(|server| workspace_command.server_name.0 == *server)
+                    })
+                    .peekable();
+                let Some(buffer) = matching_commands
+                    .peek()
+                    .map(|workspace_command| workspace_command.buffer.clone())
+                else {
+                    let message = match &server {
+                        Some(server) => format!("{command} is not available from {server}"),
+                        None => {
+                            format!("{command} is not available for this buffer's language servers")
+                        }
+                    };
+                    Self::show_unavailable_toast(workspace, message, cx);
+                    return None;
+                };
+                let mut servers = matching_commands
+                    .map(|workspace_command| CommandServer {
+                        server_id: workspace_command.server_id,
+                        server_name: server_names
+                            .get(&workspace_command.server_id)
+                            .cloned()
+                            .unwrap_or_else(|| workspace_command.server_id.to_string().into()),
+                        root_path: workspace_command.root_path.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                servers.sort_by_key(|server| server.server_id);
+                servers.dedup_by_key(|server| server.server_id);
+                Some((buffer, choose_servers(&command, servers, window, cx)))
+            })?;
+            let Some((buffer, choice)) = choice else {
+                return Ok(());
+            };
+            let Some(server_ids) = choice.await else {
+                return Ok(());
+            };
+            workspace.update_in(cx, |workspace, window, cx| {
+                let open_files = open_file_uris(workspace, cx);
+                let arguments =
+                    resolve_command_arguments(&input, &command, &buffer, &open_files, &editor, cx);
+                match arguments {
+                    Ok(arguments) => {
+                        cx.set_global(LastRunCommand {
+                            command: command.clone(),
+                            server_ids: server_ids.clone(),
+                            input,
+                        });
+                        confirm_and_run_command(
+                            workspace.weak_handle(),
+                            &editor,
+                            &lsp_store,
+                            server_ids,
+                            command,
+                            arguments,
+                            window,
+                            cx,
+                        );
+                    }
+                    Err(error) => Self::show_unavailable_toast(
+                        workspace,
+                        format!("Invalid arguments for {command}: {error}"),
+                        cx,
+                    ),
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn run_macro(
+        workspace: &mut Workspace,
+        name: String,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let settings = LspWorkspaceCommandSettings::get_global(cx);
+        let Some(steps) = settings.command_macros.get(&name).cloned() else {
+            Self::show_unavailable_toast(
+                workspace,
+                format!("No command macro is named {name}"),
+                cx,
+            );
+            return;
+        };
+        let stop_on_failure = settings.stop_macro_on_failure;
+        let Some((editor, active_buffer)) = Self::active_editor_and_buffer(workspace, cx) else {
+            Self::show_unavailable_toast(workspace, NO_ACTIVE_EDITOR_MESSAGE, cx);
+            return;
+        };
+        let buffers = Self::editor_buffers(&editor, active_buffer, cx);
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        let discovery = discover_commands(buffers, &lsp_store, cx);
+        cx.spawn_in(window, async move |workspace, cx| {
+            let (commands, _) = discovery.await?;
+            workspace.update_in(cx, |workspace, window, cx| {
+                let mut queue = Vec::with_capacity(steps.len());
+                for step in steps {
+                    let mut matching_commands = commands
+                        .iter()
+                        .filter(|workspace_command| workspace_command.command == step.command)
+                        .peekable();
+                    let Some(buffer) = matching_commands
+                        .peek()
+                        .map(|workspace_command| workspace_command.buffer.clone())
+                    else {
+                        Self::show_unavailable_toast(
+                            workspace,
+                            format!(
+                                "{} of macro {name} is not available for this buffer's language servers",
+                                step.command
+                            ),
+                            cx,
+                        );
+                        return;
+                    };
+                    let mut server_ids = matching_commands
+                        .map(|workspace_command| workspace_command.server_id)
+                        .collect::<Vec<_>>();
+                    server_ids.sort();
+                    server_ids.dedup();
+                    queue.push(QueuedCommand {
+                        buffer,
+                        command: step.command,
+                        server_ids,
+                        input: if step.arguments.is_empty() {
+                            String::new()
+                        } else {
+                            Value::from(step.arguments).to_string()
+                        },
+                    });
+                }
+                run_command_queue(
+                    workspace.weak_handle(),
+                    &editor,
+                    &lsp_store,
+                    queue,
+                    open_file_uris(workspace, cx),
+                    Some(name),
+                    stop_on_failure,
+                    window,
+                    cx,
+                );
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn dump_capabilities(
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let Some((editor, active_buffer)) = Self::active_editor_and_buffer(workspace, cx) else {
+            Self::show_unavailable_toast(workspace, NO_ACTIVE_EDITOR_MESSAGE, cx);
+            return;
+        };
+        let lsp_store = workspace.project().read(cx).lsp_store();
+        let requests = Self::editor_buffers(&editor, active_buffer, cx)
+            .into_iter()
+            .map(|buffer| {
+                lsp_store.update(cx, |lsp_store, cx| {
+                    lsp_store.workspace_commands(&buffer, cx)
+                })
+            })
+            .collect::<Vec<_>>();
+        let languages = workspace.project().read(cx).languages().clone();
+        cx.spawn_in(window, async move |workspace, cx| {
+            let mut servers = future::try_join_all(requests)
+                .await?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            servers.sort_by_key(|server| server.server_id);
+            servers.dedup_by_key(|server| server.server_id);
+            let text = cx.update(|_, cx| {
+                capabilities_text(&servers, LspWorkspaceCommandSettings::get_global(cx))
+            })??;
+            let json = languages.language_for_name("JSON").await.log_err();
+            workspace.update_in(cx, |workspace, window, cx| {
+                open_json_buffer(
+                    workspace,
+                    text,
+                    json,
+                    "LSP Workspace Commands".to_string(),
+                    window,
+                    cx,
+                );
+            })
+        })
+        .detach_and_prompt_err(
+            "Failed to list the workspace commands",
+            window,
+            cx,
+            |_, _, _| None,
+        );
+    }
+
+    fn clear_history(workspace: &mut Workspace, cx: &mut Context<Workspace>) {
+        CommandHistories::clear(history_key(workspace, cx), cx);
+        FavoriteCommands::clear(cx);
+        if cx.has_global::<LastRunCommand>() {
+            cx.remove_global::<LastRunCommand>();
+        }
+        struct HistoryClearedToast;
+        workspace.show_toast(
+            Toast::new(
+                NotificationId::unique::<HistoryClearedToast>(),
+                "Cleared the LSP workspace command history",
+            )
+            .autohide(),
+            cx,
+        );
+    }
+
+    fn show_unavailable_toast(
+        workspace: &mut Workspace,
+        message: impl Into<Cow<'static, str>>,
+        cx: &mut Context<Workspace>,
+    ) {
+        struct SelectorUnavailableToast;
+        workspace.show_toast(
+            Toast::new(
+                NotificationId::unique::<SelectorUnavailableToast>(),
+                message,
+            )
+            .autohide(),
+            cx,
+        );
+    }
+
+    /// Opens the selector in a loading state, which the commands replace once they've been
+    /// discovered, so that it opens without waiting for the servers.
+    fn new(
+        workspace: WeakEntity<Workspace>,
+        editor: Entity<Editor>,
+        buffers: Vec<Entity<Buffer>>,
+        lsp_store: Entity<LspStore>,
+        load_history: Task<()>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let lsp_store_subscription = cx.subscribe_in(&lsp_store, window, Self::on_lsp_store_event);
+        let discovery = discover_commands(buffers.clone(), &lsp_store, cx);
+        let show_commands = cx.spawn_in(window, async move |this, cx| {
+            let discovered = discovery.await;
+            load_history.await;
+            this.update_in(cx, |this, window, cx| {
+                this.show_discovered_commands(discovered, window, cx);
+            })
+            .log_err();
+        });
+        let language = buffer_language(&buffers, cx);
+        let restored_query = cx
+            .try_global::<LastQuery>()
+            .filter(|last_query| {
+                LspWorkspaceCommandSettings::get_global(cx).restore_query
+                    && last_query.language == language
+            })
+            .map(|last_query| last_query.query.clone());
+        let delegate = LspWorkspaceCommandSelectorDelegate::new(
+            cx.entity().downgrade(),
+            workspace,
+            editor,
+            buffers,
+            lsp_store,
+            cx,
+        );
+
+        let picker = cx.new(|cx| {
+            let picker = Picker::list(delegate, window, cx);
+            if let Some(query) = restored_query {
+                picker.set_query(query, window, cx);
+            }
+            picker
+        });
+        Self {
+            picker,
+            reload_commands: show_commands,
+            _lsp_store_subscription: lsp_store_subscription,
+        }
+    }
+
+    /// Lists the commands discovered when the selector opened, or dismisses it with a toast when
+    /// there aren't any.
+    fn show_discovered_commands(
+        &mut self,
+        discovered: anyhow::Result<(
+            Vec<WorkspaceCommand>,
+            HashMap<LanguageServerId, SharedString>,
+        )>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let message = match discovered {
+            Ok((commands, _)) if commands.is_empty() => NO_COMMANDS_MESSAGE.to_string(),
+            Ok((commands, server_names)) => {
+                self.picker.update(cx, |picker, cx| {
+                    picker.delegate.set_commands(commands, server_names, cx);
+                    picker.refresh(window, cx);
+                });
+                return;
+            }
+            Err(error) => format!("Failed to discover LSP workspace commands: {error:#}"),
+        };
+        self.picker
+            .read(cx)
+            .delegate
+            .workspace
+            .update(cx, |workspace, cx| {
+                Self::show_unavailable_toast(workspace, message, cx);
+            })
+            .log_err();
+        cx.emit(DismissEvent);
+    }
+
+    /// Discovers the commands of the buffers' language servers again, dropping those of servers
+    /// that have stopped. With `confirm`, a toast tells how many commands were found, or why
+    /// they couldn't be.
+    fn reload_commands(&mut self, confirm: bool, window: &mut Window, cx: &mut Context<Self>) {
+        struct RefreshedCommandsToast;
+
+        let delegate = &self.picker.read(cx).delegate;
+        let buffers = delegate.buffers.clone();
+        let lsp_store = delegate.lsp_store.clone();
+        let workspace = delegate.workspace.clone();
+        let discovery = discover_commands(buffers, &lsp_store, cx);
+        self.reload_commands = cx.spawn_in(window, async move |this, cx| {
+            let message = match discovery.await {
+                Ok((commands, server_names)) => {
+                    let Some(count) = this
+                        .update_in(cx, |this, window, cx| {
+                            this.picker.update(cx, |picker, cx| {
+                                picker.delegate.set_commands(commands, server_names, cx);
+                                picker.refresh(window, cx);
+                                picker.delegate.candidate_count
+                            })
+                        })
+                        .log_err()
+                    else {
+                        return;
+                    };
+                    format!("Refreshed LSP workspace commands: {count} found")
+                }
+                Err(error) => {
+                    log::error!(target: LOG_TARGET, "failed to discover commands: {error:#}");
+                    this.update(cx, |this, cx| {
+                        this.picker.update(cx, |picker, cx| {
+                            picker.delegate.loading = false;
+                            cx.notify();
+                        });
+                    })
+                    .log_err();
+                    format!("Failed to refresh LSP workspace commands: {error:#}")
+                }
+            };
+            if confirm {
+                workspace
+                    .update(cx, |workspace, cx| {
+                        workspace.show_toast(
+                            Toast::new(NotificationId::unique::<RefreshedCommandsToast>(), message)
+                                .autohide(),
+                            cx,
+                        );
+                    })
+                    .log_err();
+            }
+        });
+    }
+
+    /// Discovers the commands again while the selector stays open with its query, such as once a
+    /// server has finished indexing and registered more of them.
+    fn refresh_commands(
+        &mut self,
+        _: &RefreshCommands,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        CommandCache::clear(cx);
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.loading = true;
+            cx.notify();
+        });
+        self.reload_commands(true, window, cx);
+    }
+
+    fn on_lsp_store_event(
+        &mut self,
+        _: &Entity<LspStore>,
+        event: &LspStoreEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            LspStoreEvent::LanguageServerAdded(..)
+            | LspStoreEvent::LanguageServerRemoved(_)
+            | LspStoreEvent::LanguageServerCapabilitiesUpdated(_) => {
+                // The workspace clears the cache on these events too, but possibly only after
+                // the selector has been notified.
+                CommandCache::clear(cx);
+                self.reload_commands(false, window, cx);
+            }
+            _ => {}
+        }
+    }
+
+    fn toggle_favorite(&mut self, _: &ToggleFavorite, window: &mut Window, cx: &mut Context<Self>) {
+        self.picker.update(cx, |picker, cx| {
+            let delegate = &picker.delegate;
+            if let Some(CommandEntry::Command(mat)) = delegate.entries.get(delegate.selected_index)
+                && let Some(command) = delegate.commands.get(mat.candidate_id)
+            {
+                FavoriteCommands::toggle(&command.label, cx);
+                picker.refresh(window, cx);
+            }
+        });
+    }
+
+    fn copy_command_id(&mut self, _: &CopyCommandId, _: &mut Window, cx: &mut Context<Self>) {
+        struct CopiedCommandIdToast;
+
+        let delegate = &self.picker.read(cx).delegate;
+        let Some(CommandEntry::Command(mat)) = delegate.entries.get(delegate.selected_index) else {
+            return;
+        };
+        let Some(command) = delegate.commands.get(mat.candidate_id) else {
+            return;
+        };
+        let command = command.command.clone();
+        let workspace = delegate.workspace.clone();
+        cx.write_to_clipboard(ClipboardItem::new_string(command.clone()));
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(
+                        NotificationId::unique::<CopiedCommandIdToast>(),
+                        format!("Copied {command}"),
+                    )
+                    .autohide(),
+                    cx,
+                );
+            })
+            .log_err();
+    }
+
+    fn copy_keybinding(&mut self, _: &CopyKeybinding, _: &mut Window, cx: &mut Context<Self>) {
+        struct CopiedKeybindingToast;
+
+        let delegate = &self.picker.read(cx).delegate;
+        let Some(CommandEntry::Command(mat)) = delegate.entries.get(delegate.selected_index) else {
+            return;
+        };
+        let Some(command) = delegate.commands.get(mat.candidate_id) else {
+            return;
+        };
+        let snippet = keybinding_snippet(&command.command, &command.server_name.0);
+        let message = format!("Copied a keybinding for {}", command.label);
+        let workspace = delegate.workspace.clone();
+        cx.write_to_clipboard(ClipboardItem::new_string(snippet));
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(NotificationId::unique::<CopiedKeybindingToast>(), message)
+                        .autohide(),
+                    cx,
+                );
+            })
+            .log_err();
+    }
+
+    fn cycle_sort_order(
+        &mut self,
+        _: &CycleSortOrder,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        CommandSortOrder::cycle(cx);
+        self.picker
+            .update(cx, |picker, cx| picker.refresh(window, cx));
+    }
+
+    fn toggle_case_sensitive(
+        &mut self,
+        _: &ToggleCaseSensitive,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        CaseSensitiveMatching::toggle(cx);
+        self.picker
+            .update(cx, |picker, cx| picker.refresh(window, cx));
+    }
+
+    fn toggle_queued(&mut self, _: &ToggleQueued, _: &mut Window, cx: &mut Context<Self>) {
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.toggle_selected_queued();
+            cx.notify();
+        });
+    }
+
+    fn toggle_server_group(
+        &mut self,
+        _: &ToggleServerGroup,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.toggle_selected_server_group(cx);
+            picker.refresh(window, cx);
+        });
+    }
+
+    fn inspect_result(&mut self, _: &InspectResult, window: &mut Window, cx: &mut Context<Self>) {
+        self.picker.update(cx, |picker, cx| {
+            picker
+                .delegate
+                .open_arguments_prompt(false, false, true, window, cx);
+        });
+    }
+
+    fn run_on_all_servers(
+        &mut self,
+        _: &RunOnAllServers,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker
+                .delegate
+                .open_arguments_prompt(true, false, false, window, cx);
+        });
+    }
+
+    fn run_and_keep_open(
+        &mut self,
+        _: &RunAndKeepOpen,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker
+                .delegate
+                .open_arguments_prompt(false, true, false, window, cx);
+        });
+    }
+}
+
+impl Render for LspWorkspaceCommandSelector {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let width = LspWorkspaceCommandSettings::get_global(cx).modal_width();
+        v_flex()
+            .key_context("LspWorkspaceCommandSelector")
+            .w(rems(width))
+            .on_action(cx.listener(Self::toggle_favorite))
+            .on_action(cx.listener(Self::copy_command_id))
+            .on_action(cx.listener(Self::copy_keybinding))
+            .on_action(cx.listener(Self::refresh_commands))
+            .on_action(cx.listener(Self::inspect_result))
+            .on_action(cx.listener(Self::run_on_all_servers))
+            .on_action(cx.listener(Self::run_and_keep_open))
+            .on_action(cx.listener(Self::cycle_sort_order))
+            .on_action(cx.listener(Self::toggle_server_group))
+            .on_action(cx.listener(Self::toggle_case_sensitive))
+            .on_action(cx.listener(Self::toggle_queued))
+            .child(self.picker.clone())
+    }
+}
+
+impl Focusable for LspWorkspaceCommandSelector {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for LspWorkspaceCommandSelector {}
+impl ModalView for LspWorkspaceCommandSelector {}
+
+pub struct LspWorkspaceCommandSelectorDelegate {
+    lsp_workspace_command_selector: WeakEntity<LspWorkspaceCommandSelector>,
+    workspace: WeakEntity<Workspace>,
+    /// Under which the collapsed server groups are remembered.
+    workspace_id: Option<WorkspaceId>,
+    /// Under which the recently and frequently run commands are remembered.
+    history_key: String,
+    editor: Entity<Editor>,
+    buffers: Vec<Entity<Buffer>>,
+    lsp_store: Entity<LspStore>,
+    commands: Vec<WorkspaceCommand>,
+    server_names: HashMap<LanguageServerId, SharedString>,
+    /// Whether the commands come from several buffers of a multibuffer, in which case each
+    /// one names the buffer it runs against.
+    show_buffer_names: bool,
+    /// Whether the commands execute on the host of a remote or shared project.
+    runs_on_host: bool,
+    /// Shared with the matching task of each query, so that it doesn't copy them.
+    candidates: Arc<[StringMatchCandidate]>,
+    /// The number of candidates the query is matched against, fewer than all of them when the
+    /// query is scoped to a server.
+    candidate_count: usize,
+    /// How many matches are listed of how many candidates, when the matches of the query were
+    /// cut off at `max_matches`.
+    truncation: Option<(usize, usize)>,
+    /// The order of the listed commands, while there's no query to rank them by.
+    sort_order: Option<SortOrder>,
+    entries: Vec<CommandEntry>,
+    selected_index: usize,
+    /// The candidate ids of the commands to run on confirming, in the order they were queued.
+    queued: Vec<usize>,
+    /// Whether the commands are still being discovered.
+    loading: bool,
+    /// Describes the selected command in the footer, so that its id and server are read out
+    /// when the selection moves, rather than just its highlighted title.
+    selection_description: Option<SharedString>,
+}
+
+struct WorkspaceCommand {
+    server_id: LanguageServerId,
+    server_name: LanguageServerName,
+    /// The buffer the command runs against, whose URI and cursor fill the argument templates.
+    buffer: Entity<Buffer>,
+    command: String,
+    /// The title given to the command by one of the buffer's code lenses.
+    title: Option<String>,
+    /// `"{server}: {command}"`, which identifies the command across servers.
+    label: String,
+    /// The short names given to the command in the settings.
+    aliases: Vec<String>,
+    /// The root of the worktree the server was started for.
+    root_path: Option<Arc<Path>>,
+    /// The servers that provide the command for the buffer, starting with `server_id`. The
+    /// command is listed once for all of them, and not at all for the others.
+    provider_ids: Vec<LanguageServerId>,
+}
+
+impl WorkspaceCommand {
+    /// The text matched against the query: the server name, the title, the command id and the
+    /// aliases.
+    fn match_text(&self, server_name: &str) -> String {
+        let mut text = match &self.title {
+            Some(title) => format!("{server_name}: {title} {}", self.command),
+            None => format!("{server_name}: {}", self.command),
+        };
+        for alias in &self.aliases {
+            text.push(' ');
+            text.push_str(alias);
+        }
+        text
+    }
+
+    fn description(&self, server_name: &str) -> String {
+        command_description(self.title.as_deref(), &self.command, server_name)
+    }
+
+    /// Where the command id starts within the [`Self::match_text`].
+    fn command_start(&self, server_name: &str) -> usize {
+        let title_start = server_name.len() + 2;
+        match &self.title {
+            Some(title) => title_start + title.len() + 1,
+            None => title_start,
+        }
+    }
+}
+
+enum CommandEntry {
+    Header(SharedString),
+    /// The header of a server's commands, which are left out while it's collapsed.
+    ServerHeader {
+        server_name: SharedString,
+        collapsed: bool,
+        command_count: usize,
+    },
+    Command(StringMatch),
+    /// The last row when more commands match the query than are listed.
+    Truncated,
+}
+
+impl LspWorkspaceCommandSelectorDelegate {
+    fn new(
+        lsp_workspace_command_selector: WeakEntity<LspWorkspaceCommandSelector>,
+        workspace: WeakEntity<Workspace>,
+        editor: Entity<Editor>,
+        buffers: Vec<Entity<Buffer>>,
+        lsp_store: Entity<LspStore>,
+        cx: &mut App,
+    ) -> Self {
+        Self {
+            lsp_workspace_command_selector,
+            workspace_id: workspace
+                .read_with(cx, |workspace, _| workspace.database_id())
+                .ok()
+                .flatten(),
+            history_key: workspace
+                .read_with(cx, |workspace, cx| history_key(workspace, cx))
+                .unwrap_or_default(),
+            workspace,
+            editor,
+            show_buffer_names: buffers.len() > 1,
+            runs_on_host: lsp_store.read(cx).upstream_client().is_some(),
+            buffers,
+            lsp_store,
+            commands: Vec::new(),
+            server_names: HashMap::default(),
+            candidates: Arc::default(),
+            candidate_count: 0,
+            truncation: None,
+            sort_order: None,
+            entries: Vec::new(),
+            selected_index: 0,
+            queued: Vec::new(),
+            loading: true,
+            selection_description: None,
+        }
+    }
+
+    /// Replaces the listed commands. The entries and the queue are cleared until the matches are
+    /// updated, as they refer to the previous commands.
+    fn set_commands(
+        &mut self,
+        mut commands: Vec<WorkspaceCommand>,
+        mut server_names: HashMap<LanguageServerId, SharedString>,
+        cx: &App,
+    ) {
+        let primaries = duplicate_primaries(
+            commands
+                .iter()
+                .map(|command| (command.buffer.entity_id(), command.command.clone())),
+        );
+        for (ix, &primary) in primaries.iter().enumerate() {
+            if primary != ix {
+                let server_id = commands[ix].server_id;
+                commands[primary].provider_ids.push(server_id);
+            }
+        }
+        let worktree_names = commands
+            .iter()
+            .filter_map(|command| {
+                let full_path = command.buffer.read(cx).file()?.full_path(cx);
+                let root_name = full_path.components().next()?.as_os_str().to_string_lossy();
+                Some((
+                    command.server_id,
+                    SharedString::from(root_name.into_owned()),
+                ))
+            })
+            .collect();
+        disambiguate_server_names(&mut server_names, &worktree_names);
+        self.candidates = commands
+            .iter()
+            .enumerate()
+            .filter(|(candidate_id, _)| primaries[*candidate_id] == *candidate_id)
+            .map(|(candidate_id, command)| {
+                let server_name = server_names
+                    .get(&command.server_id)
+                    .map_or("", |name| name.as_ref());
+                StringMatchCandidate::new(candidate_id, &command.match_text(server_name))
+            })
+            .collect();
+        self.candidate_count = self.candidates.len();
+        self.commands = commands;
+        self.server_names = server_names;
+        self.entries.clear();
+        self.queued.clear();
+        self.loading = false;
+    }
+
+    fn toggle_selected_queued(&mut self) {
+        let Some(CommandEntry::Command(mat)) = self.entries.get(self.selected_index) else {
+            return;
+        };
+        let candidate_id = mat.candidate_id;
+        if let Some(position) = self
+            .queued
+            .iter()
+            .position(|queued| *queued == candidate_id)
+        {
+            self.queued.remove(position);
+        } else {
+            self.queued.push(candidate_id);
+        }
+    }
+
+    /// Runs the queued commands on their own servers, one after another.
+    fn run_queued(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let queue = self
+            .queued
+            .drain(..)
+            .filter_map(|candidate_id| {
+                let command = self.commands.get(candidate_id)?;
+                Some(QueuedCommand {
+                    buffer: command.buffer.clone(),
+                    command: command.command.clone(),
+                    server_ids: command.provider_ids.clone(),
+                    input: last_command_arguments(&command.command, cx).unwrap_or_default(),
+                })
+            })
+            .collect();
+        let open_files = self
+            .workspace
+            .upgrade()
+            .map(|workspace| open_file_uris(workspace.read(cx), cx))
+            .unwrap_or_default();
+        run_command_queue(
+            self.workspace.clone(),
+            &self.editor,
+            &self.lsp_store,
+            queue,
+            open_files,
+            None,
+            false,
+            window,
+            cx,
+        );
+        self.dismissed(window, cx);
+    }
+
+    /// Lists the starred commands first and the recently run ones after them, most recent at the
+    /// top, followed by the remaining matches grouped under a header for each server.
+    fn build_entries(
+        &self,
+        matches: Vec<StringMatch>,
+        favorites: &HashSet<String>,
+        pinned_header: &'static str,
+        pinned_commands: &[String],
+        collapsed_servers: &HashSet<String>,
+    ) -> Vec<CommandEntry> {
+        let (favorite_matches, matches): (Vec<_>, Vec<_>) = matches.into_iter().partition(|mat| {
+            self.commands
+                .get(mat.candidate_id)
+                .is_some_and(|command| favorites.contains(&command.label))
+        });
+        let pinned_position = |mat: &StringMatch| {
+            let command = &self.commands.get(mat.candidate_id)?.command;
+            pinned_commands.iter().position(|pinned| pinned == command)
+        };
+        let (mut pinned_matches, matches): (Vec<_>, Vec<_>) = matches
+            .into_iter()
+            .partition(|mat| pinned_position(mat).is_some());
+        pinned_matches.sort_by_key(pinned_position);
+
+        let mut entries = Vec::new();
+        if !favorite_matches.is_empty() {
+            entries.push(CommandEntry::Header("Favorites".into()));
+            entries.extend(favorite_matches.into_iter().map(CommandEntry::Command));
+        }
+        if !pinned_matches.is_empty() {
+            entries.push(CommandEntry::Header(pinned_header.into()));
+            entries.extend(pinned_matches.into_iter().map(CommandEntry::Command));
+        }
+        entries.extend(self.group_by_server(matches, collapsed_servers));
+        entries
+    }
+
+    /// Adds the usage of each command to its match score, so that the commands run most often
+    /// and most recently rank above equally good matches.
+    fn boost_by_usage(&self, matches: &mut [StringMatch], usage: &CommandUsage, weight: f64) {
+        let now = SystemTime::now();
+        for mat in matches.iter_mut() {
+            if let Some(command) = self.commands.get(mat.candidate_id) {
+                mat.score += weight * usage.frecency(&command.command, now);
+            }
+        }
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+
+    /// Ranks the commands that the query is an alias of above all other matches.
+    fn boost_aliases(&self, matches: &mut [StringMatch], query: &str) {
+        let mut boosted = false;
+        for mat in matches.iter_mut() {
+            if let Some(command) = self.commands.get(mat.candidate_id)
+                && command
+                    .aliases
+                    .iter()
+                    .any(|alias| alias.eq_ignore_ascii_case(query))
+            {
+                mat.score += ALIAS_MATCH_BONUS;
+                boosted = true;
+            }
+        }
+        if boosted {
+            matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+    }
+
+    /// Returns the candidates of the servers named by a prefix of the query, like `eslint>fix` or
+    /// `@eslint fix`, along with the rest of the query. Prefixes that name no server are matched
+    /// as part of the query instead.
+    fn scope_to_server<'a>(
+        &self,
+        query: &'a str,
+    ) -> Option<(Arc<[StringMatchCandidate]>, &'a str)> {
+        let (prefix, rest) = split_server_prefix(query)?;
+        let prefix = prefix.to_lowercase();
+        let server_ids = self
+            .server_names
+            .iter()
+            .filter(|(_, name)| name.to_lowercase().starts_with(&prefix))
+            .map(|(server_id, _)| *server_id)
+            .collect::<HashSet<_>>();
+        if server_ids.is_empty() {
+            return None;
+        }
+        let candidates = self
+            .candidates
+            .iter()
+            .filter(|candidate| {
+                self.commands.get(candidate.id).is_some_and(|command| {
+                    command
+                        .provider_ids
+                        .iter()
+                        .any(|server_id| server_ids.contains(server_id))
+                })
+            })
+            .cloned()
+            .collect();
+        Some((candidates, rest))
+    }
+
+    /// Groups the matches under a header for each server, ordering the groups by their best match.
+    fn group_by_server(
+        &self,
+        matches: Vec<StringMatch>,
+        collapsed_servers: &HashSet<String>,
+    ) -> Vec<CommandEntry> {
+        let mut groups = IndexMap::<LanguageServerId, Vec<StringMatch>>::default();
+        for mat in matches {
+            if let Some(command) = self.commands.get(mat.candidate_id) {
+                groups.entry(command.server_id).or_default().push(mat);
+            }
+        }
+
+        let mut entries = Vec::new();
+        for (server_id, group) in groups {
+            let server_name = self
+                .server_names
+                .get(&server_id)
+                .cloned()
+                .unwrap_or_else(|| server_id.to_string().into());
+            let collapsed = collapsed_servers.contains(server_name.as_ref());
+            entries.push(CommandEntry::ServerHeader {
+                server_name,
+                collapsed,
+                command_count: group.len(),
+            });
+            if !collapsed {
+                entries.extend(group.into_iter().map(CommandEntry::Command));
+            }
+        }
+        entries
+    }
+
+    /// Collapses or expands the group of the selected server header or command.
+    fn toggle_selected_server_group(&mut self, cx: &mut App) {
+        let Some(workspace_id) = self.workspace_id else {
+            return;
+        };
+        let server_name = match self.entries.get(self.selected_index) {
+            Some(CommandEntry::ServerHeader { server_name, .. }) => server_name.clone(),
+            Some(CommandEntry::Command(mat)) => {
+                let Some(server_name) = self
+                    .commands
+                    .get(mat.candidate_id)
+                    .and_then(|command| self.server_names.get(&command.server_id))
+                else {
+                    return;
+                };
+                server_name.clone()
+            }
+            Some(CommandEntry::Header(_) | CommandEntry::Truncated) | None => return,
+        };
+        CollapsedServers::toggle(workspace_id, &server_name, cx);
+    }
+
+    /// Opens the arguments prompt for the selected command, to run it on its own server or, with
+    /// `all_servers`, on every server that provides it. With `keep_open`, the selector reopens
+    /// once the command has started so that another one can be run.
+    fn open_arguments_prompt(
+        &mut self,
+        all_servers: bool,
+        keep_open: bool,
+        inspect_result: bool,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        // Without a command to run, such as when nothing matches the query, keep the selector
+        // open rather than dismissing it along with the query.
+        let Some(CommandEntry::Command(mat)) = self.entries.get(self.selected_index) else {
+            return;
+        };
+        let Some(workspace_command) = self.commands.get(mat.candidate_id) else {
+            return;
+        };
+        let command = workspace_command.command.clone();
+        let server_ids = if all_servers {
+            let mut server_ids = self
+                .commands
+                .iter()
+                .filter(|other| other.command == command)
+                .map(|other| other.server_id)
+                .collect::<Vec<_>>();
+            server_ids.sort();
+            server_ids.dedup();
+            server_ids
+        } else {
+            workspace_command.provider_ids.clone()
+        };
+        let lsp_store = self.lsp_store.read(cx);
+        let (server_ids, unavailable_servers): (Vec<_>, Vec<_>) =
+            server_ids.into_iter().partition(|server_id| {
+                ServerStatus::of(lsp_store, *server_id) == ServerStatus::Running
+            });
+        let mut restart = None;
+        if server_ids.is_empty() {
+            let restart_stopped_servers =
+                LspWorkspaceCommandSettings::get_global(cx).restart_stopped_servers;
+            let stopped_servers = unavailable_servers
+                .iter()
+                .filter(|server_id| {
+                    ServerStatus::of(lsp_store, **server_id) == ServerStatus::Stopped
+                })
+                .filter_map(|server_id| {
+                    let stopped = self
+                        .commands
+                        .iter()
+                        .find(|command| command.server_id == *server_id)?;
+                    Some(stopped.server_name.clone())
+                })
+                .collect::<HashSet<_>>();
+            if restart_stopped_servers != RestartStoppedServers::Never
+                && !stopped_servers.is_empty()
+            {
+                restart = Some((
+                    stopped_servers,
+                    restart_stopped_servers == RestartStoppedServers::Ask,
+                ));
+            }
+        }
+        if server_ids.is_empty() && restart.is_none() {
+            let message = unavailable_servers
+                .iter()
+                .map(|server_id| {
+                    let server_name = self
+                        .server_names
+                        .get(server_id)
+                        .cloned()
+                        .unwrap_or_else(|| server_id.to_string().into());
+                    match ServerStatus::of(lsp_store, *server_id) {
+                        ServerStatus::Starting => format!("{server_name} is still starting"),
+                        _ => format!("{server_name} is not running"),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    LspWorkspaceCommandSelector::show_unavailable_toast(workspace, message, cx);
+                })
+                .log_err();
+            return;
+        }
+        let servers = self.servers_providing(&command, &server_ids);
+        let workspace = self.workspace.clone();
+        let editor = self.editor.clone();
+        let buffers = self.buffers.clone();
+        let buffer = workspace_command.buffer.clone();
+        let lsp_store = self.lsp_store.clone();
+        // Open the prompt once the selector is gone, so that dismissing the prompt
+        // returns focus to the editor rather than to the selector.
+        window.defer(cx, move |window, cx| {
+            let choice = match restart {
+                Some((server_names, ask)) => restart_for_command(
+                    workspace.clone(),
+                    lsp_store.clone(),
+                    editor.clone(),
+                    buffers,
+                    command.clone(),
+                    server_names,
+                    ask,
+                    window,
+                    cx,
+                ),
+                None => choose_servers(&command, servers, window, cx),
+            };
+            window
+                .spawn(cx, async move |cx| {
+                    let Some(server_ids) = choice.await else {
+                        return;
+                    };
+                    workspace
+                        .update_in(cx, |workspace, window, cx| {
+                            let workspace_handle = workspace.weak_handle();
+                            workspace.toggle_modal(window, cx, move |window, cx| {
+                                CommandArgumentsPrompt::new(
+                                    workspace_handle,
+                                    editor,
+                                    buffer,
+                                    command,
+                                    server_ids,
+                                    lsp_store,
+                                    keep_open,
+                                    inspect_result,
+                                    window,
+                                    cx,
+                                )
+                            });
+                        })
+                        .log_err();
+                })
+                .detach();
+        });
+        self.dismissed(window, cx);
+    }
+
+    /// The name and root of each of the servers that provide `command`.
+    fn servers_providing(
+        &self,
+        command: &str,
+        server_ids: &[LanguageServerId],
+    ) -> Vec<CommandServer> {
+        let mut servers = self
+            .commands
+            .iter()
+            .filter(|other| other.command == command && server_ids.contains(&other.server_id))
+            .map(|other| CommandServer {
+                server_id: other.server_id,
+                server_name: self
+                    .server_names
+                    .get(&other.server_id)
+                    .cloned()
+                    .unwrap_or_else(|| other.server_id.to_string().into()),
+                root_path: other.root_path.clone(),
+            })
+            .collect::<Vec<_>>();
+        servers.sort_by_key(|server| server.server_id);
+        servers.dedup_by_key(|server| server.server_id);
+        servers
+    }
+}
+
+impl PickerDelegate for LspWorkspaceCommandSelectorDelegate {
+    type ListItem = AnyElement;
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        let mut server_names = self
+            .candidates
+            .iter()
+            .filter_map(|candidate| {
+                let command = self.commands.get(candidate.id)?;
+                self.server_names.get(&command.server_id)
+            })
+            .collect::<Vec<_>>();
+        server_names.sort();
+        server_names.dedup();
+        let commands = match self.candidate_count {
+            1 => "1 workspace command".to_string(),
+            count => format!("{count} workspace commands"),
+        };
+        if server_names.is_empty() {
+            format!("Select from {commands}…").into()
+        } else {
+            let server_names = server_names
+                .iter()
+                .map(|name| name.as_ref())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("Select from {commands} of {server_names}…").into()
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        // The footer shows that the commands are being discovered.
+        if self.loading {
+            None
+        } else if self.candidates.is_empty() {
+            Some(NO_COMMANDS_MESSAGE.into())
+        } else {
+            Some("No matches".into())
+        }
+    }
+
+    fn can_select(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) -> bool {
+        matches!(
+            self.entries.get(ix),
+            Some(CommandEntry::Command(_) | CommandEntry::ServerHeader { .. })
+        )
+    }
+
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if let Some(CommandEntry::ServerHeader { .. }) = self.entries.get(self.selected_index) {
+            self.toggle_selected_server_group(cx);
+            cx.defer_in(window, |picker, window, cx| picker.refresh(window, cx));
+            return;
+        }
+        if !self.queued.is_empty() {
+            self.run_queued(window, cx);
+            return;
+        }
+        self.open_arguments_prompt(secondary, false, false, window, cx);
+    }
+
+    fn confirm_input(
+        &mut self,
+        secondary: bool,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        self.open_arguments_prompt(secondary, true, false, window, cx);
+    }
+
+    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.lsp_workspace_command_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+        let is_command = |entry: &&CommandEntry| matches!(entry, CommandEntry::Command(_));
+        self.selection_description = match self.entries.get(ix) {
+            Some(CommandEntry::Command(mat)) => {
+                self.commands.get(mat.candidate_id).map(|command| {
+                    let server_name = self
+                        .server_names
+                        .get(&command.server_id)
+                        .map_or("", |name| name.as_ref());
+                    let position = self.entries[..ix].iter().filter(is_command).count() + 1;
+                    let count = self.entries.iter().filter(is_command).count();
+                    format!(
+                        "{}, {position} of {count}",
+                        command.description(server_name)
+                    )
+                    .into()
+                })
+            }
+            _ => None,
+        };
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        cx.set_global(LastQuery {
+            query: query.clone(),
+            language: buffer_language(&self.buffers, cx),
+        });
+        let background = cx.background_executor().clone();
+        let (candidates, query) = match self.scope_to_server(&query) {
+            Some((candidates, rest)) => (candidates, rest.to_string()),
+            None => (self.candidates.clone(), query.clone()),
+        };
+        self.candidate_count = candidates.len();
+        let candidate_count = candidates.len();
+        let max_matches = LspWorkspaceCommandSettings::get_global(cx).max_matches;
+        let case_sensitive = CaseSensitiveMatching::is_enabled(cx);
+        let literal_matches = query.strip_prefix('=').map(|literal| {
+            let candidates = candidates.iter().filter_map(|candidate| {
+                let command = self.commands.get(candidate.id)?;
+                let server_name = self
+                    .server_names
+                    .get(&command.server_id)
+                    .map_or("", |name| name.as_ref());
+                Some((
+                    candidate,
+                    command.command.as_str(),
+                    command.command_start(server_name),
+                ))
+            });
+            let mut matches = substring_matches(candidates, literal.trim(), case_sensitive);
+            matches.truncate(max_matches);
+            matches
+        });
+        cx.spawn_in(window, async move |this, cx| {
+            let mut matches = if let Some(literal_matches) = literal_matches {
+                literal_matches
+            } else if query.is_empty() {
+                candidates
+                    .iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string.clone(),
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                // The picker drops this task when the query changes again, so typing quickly
+                // only matches the final query.
+                background.timer(MATCH_DEBOUNCE).await;
+                let terms = query.split_whitespace().collect::<Vec<_>>();
+                if terms.len() > 1 {
+                    let mut term_matches = Vec::with_capacity(terms.len());
+                    for term in terms {
+                        term_matches.push(
+                            match_strings(
+                                &candidates,
+                                term,
+                                case_sensitive,
+                                true,
+                                candidates.len(),
+                                &Default::default(),
+                                background.clone(),
+                            )
+                            .await,
+                        );
+                    }
+                    let mut matches = intersect_term_matches(term_matches);
+                    matches.truncate(max_matches);
+                    matches
+                } else {
+                    match_strings(
+                        &candidates,
+                        &query,
+                        case_sensitive,
+                        true,
+                        max_matches,
+                        &Default::default(),
+                        background,
+                    )
+                    .await
+                }
+            };
+
+            this.update_in(cx, |this, window, cx| {
+                this.delegate.truncation = (!query.is_empty()
+                    && matches.len() >= max_matches
+                    && candidate_count > max_matches)
+                    .then_some((matches.len(), candidate_count));
+                let sort_order = query.is_empty().then(|| {
+                    cx.try_global::<CommandSortOrder>()
+                        .map_or_else(SortOrder::default, |order| order.0)
+                });
+                this.delegate.sort_order = sort_order;
+                let history = CommandHistories::get(&this.delegate.history_key, cx);
+                let (pinned_header, pinned_commands) = match sort_order {
+                    Some(SortOrder::Recent) => (
+                        "Recently Used",
+                        history
+                            .map(|history| history.recent.0.iter().cloned().collect())
+                            .unwrap_or_default(),
+                    ),
+                    Some(SortOrder::Frequent) => (
+                        "Most Used",
+                        history
+                            .map(|history| history.usage.most_used())
+                            .unwrap_or_default(),
+                    ),
+                    Some(SortOrder::Alphabetical) | None => ("", Vec::new()),
+                };
+                if !query.is_empty()
+                    && let Some(usage) = history.map(|history| &history.usage)
+                {
+                    let weight = LspWorkspaceCommandSettings::get_global(cx).frecency_weight;
+                    this.delegate.boost_by_usage(&mut matches, usage, weight);
+                }
+                if !query.is_empty() {
+                    this.delegate.boost_aliases(&mut matches, query.trim());
+                }
+                let no_favorites = HashSet::default();
+                let favorites = cx
+                    .try_global::<FavoriteCommands>()
+                    .map_or(&no_favorites, |favorites| &favorites.0);
+                let no_collapsed_servers = HashSet::default();
+                let collapsed_servers = this
+                    .delegate
+                    .workspace_id
+                    .and_then(|workspace_id| {
+                        cx.try_global::<CollapsedServers>()?
+                            .0
+                            .get(&i64::from(workspace_id))
+                    })
+                    .unwrap_or(&no_collapsed_servers);
+                this.delegate.entries = this.delegate.build_entries(
+                    matches,
+                    favorites,
+                    pinned_header,
+                    &pinned_commands,
+                    collapsed_servers,
+                );
+                if this.delegate.truncation.is_some() {
+                    this.delegate.entries.push(CommandEntry::Truncated);
+                }
+                let selected_index = this
+                    .delegate
+                    .selected_index
+                    .min(this.delegate.entries.len().saturating_sub(1));
+                this.set_selected_index(selected_index, Some(Direction::Down), true, window, cx);
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_footer(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<AnyElement> {
+        let footer = h_flex()
+            .w_full()
+            .px_2()
+            .py_1()
+            .border_t_1()
+            .border_color(cx.theme().colors().border_variant);
+        // Without it, the empty list while loading would look like there are no commands.
+        let loading = self.loading.then(|| {
+            h_flex()
+                .gap_1()
+                .child(
+                    Icon::new(IconName::ArrowCircle)
+                        .size(IconSize::Small)
+                        .color(Color::Muted)
+                        .with_animation(
+                            "discovering-commands",
+                            Animation::new(Duration::from_secs(2)).repeat(),
+                            |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
+                        ),
+                )
+                .child(
+                    Label::new("Discovering commands…")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+        });
+        let truncation = self.truncation.map(|(shown, total)| {
+            Label::new(format!(
+                "Showing the best {shown} matches of {total} commands"
+            ))
+            .size(LabelSize::Small)
+            .color(Color::Muted)
+        });
+        let queued = (!self.queued.is_empty()).then(|| {
+            Label::new(match self.queued.len() {
+                1 => "1 command queued".to_string(),
+                count => format!("{count} commands queued"),
+            })
+            .size(LabelSize::Small)
+            .color(Color::Accent)
+        });
+        let selection_description = self
+            .selection_description
+            .clone()
+            .filter(|_| !self.loading)
+            .map(|description| {
+                Label::new(description)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .truncate()
+            });
+        // The order only applies without a query, where nothing is truncated.
+        let sort_order = self.sort_order.filter(|_| truncation.is_none());
+        Some(
+            footer
+                .justify_between()
+                .child(
+                    h_flex()
+                        .min_w_0()
+                        .gap_2()
+                        .children(loading)
+                        .children(truncation)
+                        .children(queued)
+                        .children(selection_description),
+                )
+                .child(
+                    h_flex()
+                        .gap_1()
+                        .when_some(sort_order, |this, sort_order| {
+                            this.child(
+                                Button::new("sort-order", sort_order.label())
+                                    .label_size(LabelSize::Small)
+                                    .key_binding(
+                                        KeyBinding::for_action(&CycleSortOrder, window, cx)
+                                            .map(|binding| binding.size(rems_from_px(12.))),
+                                    )
+                                    .on_click(|_, window, cx| {
+                                        window.dispatch_action(CycleSortOrder.boxed_clone(), cx)
+                                    }),
+                            )
+                        })
+                        .child(
+                            IconButton::new("case-sensitive", IconName::CaseSensitive)
+                                .icon_size(IconSize::Small)
+                                .toggle_state(CaseSensitiveMatching::is_enabled(cx))
+                                .tooltip(|window, cx| {
+                                    Tooltip::for_action(
+                                        "Match Case",
+                                        &ToggleCaseSensitive,
+                                        window,
+                                        cx,
+                                    )
+                                })
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(ToggleCaseSensitive.boxed_clone(), cx)
+                                }),
+                        )
+                        .child(
+                            IconButton::new("refresh-commands", IconName::RotateCw)
+                                .icon_size(IconSize::Small)
+                                .disabled(self.loading)
+                                .tooltip(|window, cx| {
+                                    Tooltip::for_action(
+                                        "Refresh Commands",
+                                        &RefreshCommands,
+                                        window,
+                                        cx,
+                                    )
+                                })
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(RefreshCommands.boxed_clone(), cx)
+                                }),
+                        ),
+                )
+                .into_any_element(),
+        )
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        match self.entries.get(ix)? {
+            CommandEntry::Header(server_name) => Some(
+                div()
+                    .px_2()
+                    .pb_1()
+                    .when(ix > 0, |this| {
+                        this.mt_1()
+                            .pt_2()
+                            .border_t_1()
+                            .border_color(cx.theme().colors().border_variant)
+                    })
+                    .child(
+                        Label::new(server_name.clone())
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .into_any_element(),
+            ),
+            CommandEntry::Truncated => Some(
+                div()
+                    .px_2()
+                    .py_1()
+                    .child(
+                        Label::new("…refine your search to see more")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .into_any_element(),
+            ),
+            CommandEntry::ServerHeader {
+                server_name,
+                collapsed,
+                command_count,
+            } => Some(
+                div()
+                    .when(ix > 0, |this| {
+                        this.mt_1()
+                            .border_t_1()
+                            .border_color(cx.theme().colors().border_variant)
+                    })
+                    .child(
+                        ListItem::new(ix)
+                            .inset(true)
+                            .spacing(ListItemSpacing::Dense)
+                            .toggle_state(selected)
+                            .start_slot(
+                                Icon::new(if *collapsed {
+                                    IconName::ChevronRight
+                                } else {
+                                    IconName::ChevronDown
+                                })
+                                .size(IconSize::XSmall)
+                                .color(Color::Muted),
+                            )
+                            .child(
+                                Label::new(server_name.clone())
+                                    .size(LabelSize::XSmall)
+                                    .color(Color::Muted),
+                            )
+                            .end_slot::<Label>(collapsed.then(|| {
+                                Label::new(match command_count {
+                                    1 => "1 command".to_string(),
+                                    count => format!("{count} commands"),
+                                })
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted)
+                            })),
+                    )
+                    .into_any_element(),
+            ),
+            CommandEntry::Command(mat) => {
+                let command = self.commands.get(mat.candidate_id)?;
+                let is_favorite = cx
+                    .try_global::<FavoriteCommands>()
+                    .is_some_and(|favorites| favorites.0.contains(&command.label));
+                let is_queued = self.queued.contains(&mat.candidate_id);
+                let last_status = LastCommandStatuses::get(command.server_id, &command.command, cx)
+                    .map(|result| match result {
+                        Ok(_) => (
+                            IconName::Check,
+                            Color::Success,
+                            "Succeeded when last run".into(),
+                        ),
+                        Err(error) => (
+                            IconName::XCircle,
+                            Color::Error,
+                            SharedString::from(format!("Failed when last run: {error}")),
+                        ),
+                    });
+                let server_name = self
+                    .server_names
+                    .get(&command.server_id)
+                    .map_or("", |name| name.as_ref());
+                let title_start = server_name.len() + 2;
+                let (title, command_id) = match &command.title {
+                    Some(title) => {
+                        let command_start = command.command_start(server_name);
+                        let command_id = HighlightedLabel::new(
+                            command.command.clone(),
+                            segment_positions(&mat.positions, command_start, &command.command),
+                        )
+                        .size(LabelSize::Small)
+                        .color(Color::Muted);
+                        (title.clone(), Some(command_id))
+                    }
+                    None => (command.command.clone(), None),
+                };
+                let aliases = (!command.aliases.is_empty()).then(|| {
+                    let aliases = command.aliases.join(" ");
+                    let aliases_start = mat.string.len().saturating_sub(aliases.len());
+                    let positions = segment_positions(&mat.positions, aliases_start, &aliases);
+                    HighlightedLabel::new(aliases, positions)
+                        .size(LabelSize::Small)
+                        .color(Color::Accent)
+                });
+                let title_positions = segment_positions(&mat.positions, title_start, &title);
+                let server_name_positions = segment_positions(&mat.positions, 0, server_name);
+                let providers = (command.provider_ids.len() > 1).then(|| {
+                    let provider_names = command
+                        .provider_ids
+                        .iter()
+                        .map(|server_id| {
+                            self.server_names
+                                .get(server_id)
+                                .cloned()
+                                .unwrap_or_else(|| server_id.to_string().into())
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    div()
+                        .id(("providers", ix))
+                        .child(
+                            Label::new(format!("×{}", command.provider_ids.len()))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .tooltip(Tooltip::text(format!("Provided by {provider_names}")))
+                });
+                let takes_arguments =
+                    LspWorkspaceCommandSettings::get_global(cx).takes_arguments(&command.command);
+                let status = ServerStatus::of(self.lsp_store.read(cx), command.server_id);
+                let buffer_name = self
+                    .show_buffer_names
+                    .then(|| {
+                        let file = command.buffer.read(cx).file()?;
+                        Some(file.file_name(cx).to_string_lossy().into_owned())
+                    })
+                    .flatten();
+                Some(
+                    ListItem::new(ix)
+                        .inset(true)
+                        .spacing(ListItemSpacing::Sparse)
+                        .toggle_state(selected)
+                        .disabled(status != ServerStatus::Running)
+                        .tooltip(Tooltip::text(command.description(server_name)))
+                        .start_slot::<Icon>(is_queued.then(|| {
+                            Icon::new(IconName::Check)
+                                .size(IconSize::Small)
+                                .color(Color::Accent)
+                        }))
+                        .child(
+                            v_flex()
+                                .child(
+                                    HighlightedLabel::new(title, title_positions)
+                                        .when(status != ServerStatus::Running, |label| {
+                                            label.color(Color::Disabled)
+                                        }),
+                                )
+                                .child(
+                                    h_flex()
+                                        .gap_1()
+                                        .child(Indicator::dot().color(status.color()))
+                                        .children(last_status.map(|(icon, color, tooltip)| {
+                                            div()
+                                                .id(("last-status", ix))
+                                                .child(
+                                                    Icon::new(icon)
+                                                        .size(IconSize::XSmall)
+                                                        .color(color),
+                                                )
+                                                .tooltip(Tooltip::text(tooltip))
+                                        }))
+                                        .child(
+                                            HighlightedLabel::new(
+                                                server_name.to_string(),
+                                                server_name_positions,
+                                            )
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted),
+                                        )
+                                        .children(providers)
+                                        .children(command_id)
+                                        .children(aliases)
+                                        .children(buffer_name.map(|buffer_name| {
+                                            Label::new(buffer_name)
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted)
+                                        }))
+                                        .when(takes_arguments, |this| {
+                                            this.child(
+                                                Label::new("takes arguments")
+                                                    .size(LabelSize::Small)
+                                                    .color(Color::Accent),
+                                            )
+                                        })
+                                        .when(self.runs_on_host, |this| {
+                                            this.child(
+                                                Label::new("on host")
+                                                    .size(LabelSize::Small)
+                                                    .color(Color::Muted),
+                                            )
+                                        }),
+                                ),
+                        )
+                        .end_slot::<Icon>(is_favorite.then(|| {
+                            Icon::new(IconName::StarFilled)
+                                .size(IconSize::Small)
+                                .color(Color::Accent)
+                        }))
+                        .into_any_element(),
+                )
+            }
+        }
+    }
+}
+
+/// Whether each command succeeded when it was last run on each server this session, which the
+/// selector shows next to the command. Forgotten whenever a server starts or stops.
+#[derive(Default)]
+struct LastCommandStatuses(HashMap<(LanguageServerId, String), Result<(), SharedString>>);
+
+impl Global for LastCommandStatuses {}
+
+impl LastCommandStatuses {
+    fn get<'a>(
+        server_id: LanguageServerId,
+        command: &str,
+        cx: &'a App,
+    ) -> Option<&'a Result<(), SharedString>> {
+        cx.try_global::<Self>()?
+            .0
+            .get(&(server_id, command.to_string()))
+    }
+
+    fn record(
+        server_id: LanguageServerId,
+        command: &str,
+        result: &Result<usize, SharedString>,
+        cx: &mut App,
+    ) {
+        let status = result.as_ref().map(|_| ()).map_err(Clone::clone);
+        cx.default_global::<Self>()
+            .0
+            .insert((server_id, command.to_string()), status);
+    }
+
+    fn clear(cx: &mut App) {
+        if cx.has_global::<Self>() {
+            cx.remove_global::<Self>();
+        }
+    }
+}
+
+const FAVORITE_COMMANDS_KEY: &str = "lsp_workspace_command_selector_favorites";
+
+/// The labels of the starred commands, which include the server name so that a command
+/// can be starred for one server but not another.
+#[derive(Default)]
+struct FavoriteCommands(HashSet<String>);
+
+impl Global for FavoriteCommands {}
+
+impl FavoriteCommands {
+    fn load(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let favorites = cx
+                .background_spawn(async { KEY_VALUE_STORE.read_kvp(FAVORITE_COMMANDS_KEY) })
+                .await?;
+            if let Some(favorites) = favorites {
+                let favorites = serde_json::from_str::<Vec<String>>(&favorites)?;
+                cx.update(|cx| cx.default_global::<Self>().0.extend(favorites))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn toggle(label: &str, cx: &mut App) {
+        let favorites = &mut cx.default_global::<Self>().0;
+        if !favorites.remove(label) {
+            favorites.insert(label.to_string());
+        }
+        let mut labels = favorites.iter().cloned().collect::<Vec<_>>();
+        labels.sort();
+        cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .write_kvp(
+                    FAVORITE_COMMANDS_KEY.to_string(),
+                    serde_json::to_string(&labels)?,
+                )
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn clear(cx: &mut App) {
+        cx.default_global::<Self>().0.clear();
+        cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .delete_kvp(FAVORITE_COMMANDS_KEY.to_string())
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+const COLLAPSED_SERVERS_KEY: &str = "lsp_workspace_command_selector_collapsed_servers";
+
+/// The names of the servers whose commands are collapsed, for each workspace.
+#[derive(Default)]
+struct CollapsedServers(HashMap<i64, HashSet<String>>);
+
+impl Global for CollapsedServers {}
+
+impl CollapsedServers {
+    fn load(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let collapsed = cx
+                .background_spawn(async { KEY_VALUE_STORE.read_kvp(COLLAPSED_SERVERS_KEY) })
+                .await?;
+            if let Some(collapsed) = collapsed {
+                let collapsed = serde_json::from_str::<HashMap<i64, HashSet<String>>>(&collapsed)?;
+                cx.update(|cx| cx.default_global::<Self>().0.extend(collapsed))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn toggle(workspace_id: WorkspaceId, server_name: &str, cx: &mut App) {
+        let collapsed = &mut cx.default_global::<Self>().0;
+        let servers = collapsed.entry(workspace_id.into()).or_default();
+        if !servers.remove(server_name) {
+            servers.insert(server_name.to_string());
+        }
+        collapsed.retain(|_, servers| !servers.is_empty());
+        let collapsed = serde_json::to_string(&collapsed);
+        cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .write_kvp(COLLAPSED_SERVERS_KEY.to_string(), collapsed?)
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+/// Whether a server can run commands, shown next to each of its commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ServerStatus {
+    Running,
+    Starting,
+    Stopped,
+}
+
+impl ServerStatus {
+    fn of(lsp_store: &LspStore, server_id: LanguageServerId) -> Self {
+        match lsp_store.as_local() {
+            Some(local) => match local.language_servers.get(&server_id) {
+                Some(LanguageServerState::Running { .. }) => Self::Running,
+                Some(LanguageServerState::Starting { .. }) => Self::Starting,
+                None => Self::Stopped,
+            },
+            // The host only reports its servers once they're running.
+            None if lsp_store
+                .language_server_statuses()
+                .any(|(id, _)| id == server_id) =>
+            {
+                Self::Running
+            }
+            None => Self::Stopped,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Running => Color::Success,
+            Self::Starting => Color::Warning,
+            Self::Stopped => Color::Error,
+        }
+    }
+}
+
+/// How the commands are ordered while there's no query to rank them by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SortOrder {
+    #[default]
+    Recent,
+    Alphabetical,
+    Frequent,
+}
+
+impl SortOrder {
+    fn next(self) -> Self {
+        match self {
+            Self::Recent => Self::Alphabetical,
+            Self::Alphabetical => Self::Frequent,
+            Self::Frequent => Self::Recent,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Recent => "Recently used first",
+            Self::Alphabetical => "Alphabetical",
+            Self::Frequent => "Most used first",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::Recent => "recent",
+            Self::Alphabetical => "alphabetical",
+            Self::Frequent => "frequent",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        [Self::Recent, Self::Alphabetical, Self::Frequent]
+            .into_iter()
+            .find(|order| order.key() == key)
+    }
+}
+
+const SORT_ORDER_KEY: &str = "lsp_workspace_command_selector_sort_order";
+
+struct CommandSortOrder(SortOrder);
+
+impl Global for CommandSortOrder {}
+
+impl CommandSortOrder {
+    fn load(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let key = cx
+                .background_spawn(async { KEY_VALUE_STORE.read_kvp(SORT_ORDER_KEY) })
+                .await?;
+            if let Some(order) = key.as_deref().and_then(SortOrder::from_key) {
+                cx.update(|cx| cx.set_global(Self(order)))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn cycle(cx: &mut App) {
+        let order = cx
+            .try_global::<Self>()
+            .map(|order| order.0)
+            .unwrap_or_default()
+            .next();
+        cx.set_global(Self(order));
+        cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .write_kvp(SORT_ORDER_KEY.to_string(), order.key().to_string())
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+const CASE_SENSITIVE_KEY: &str = "lsp_workspace_command_selector_case_sensitive";
+
+/// Whether the query's uppercase characters only match uppercase characters of the commands.
+struct CaseSensitiveMatching(bool);
+
+impl Global for CaseSensitiveMatching {}
+
+impl CaseSensitiveMatching {
+    fn load(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let key = cx
+                .background_spawn(async { KEY_VALUE_STORE.read_kvp(CASE_SENSITIVE_KEY) })
+                .await?;
+            if let Some(enabled) = key.as_deref().and_then(|key| key.parse().ok()) {
+                cx.update(|cx| cx.set_global(Self(enabled)))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn is_enabled(cx: &App) -> bool {
+        cx.try_global::<Self>().is_some_and(|matching| matching.0)
+    }
+
+    fn toggle(cx: &mut App) {
+        let enabled = !Self::is_enabled(cx);
+        cx.set_global(Self(enabled));
+        cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .write_kvp(CASE_SENSITIVE_KEY.to_string(), enabled.to_string())
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+/// The most recent command run through the arguments prompt or [`RunByName`], which
+/// [`RunLast`] runs again.
+///
+/// The arguments are kept unresolved, so that their templates follow the active editor.
+struct LastRunCommand {
+    command: String,
+    server_ids: Vec<LanguageServerId>,
+    input: String,
+}
+
+impl Global for LastRunCommand {}
+
+/// The query that the selector was last closed with, restored on opening it again for a buffer
+/// of the same language.
+struct LastQuery {
+    query: String,
+    language: Option<LanguageName>,
+}
+
+impl Global for LastQuery {}
+
+/// The language of the buffer that the selector runs commands against first.
+fn buffer_language(buffers: &[Entity<Buffer>], cx: &App) -> Option<LanguageName> {
+    let language = buffers.first()?.read(cx).language()?;
+    Some(language.name())
+}
+
+const COMMAND_HISTORY_KEY_PREFIX: &str = "lsp_workspace_command_selector_history:";
+
+/// Identifies a workspace by the roots of its worktrees, so that its run commands are
+/// remembered across restarts.
+fn history_key(workspace: &Workspace, cx: &App) -> String {
+    let mut roots = workspace
+        .visible_worktrees(cx)
+        .map(|worktree| worktree.read(cx).abs_path().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    roots.sort();
+    roots.join("\n")
+}
+
+/// The recently and frequently run commands of each workspace, loaded from the database the
+/// first time they're needed.
+#[derive(Default)]
+struct CommandHistories(HashMap<String, CommandHistory>);
+
+impl Global for CommandHistories {}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CommandHistory {
+    recent: RecentCommands,
+    usage: CommandUsage,
+}
+
+impl CommandHistories {
+    fn get<'a>(key: &str, cx: &'a App) -> Option<&'a CommandHistory> {
+        cx.try_global::<Self>()?.0.get(key)
+    }
+
+    /// Loads the history of the workspace, unless it has been loaded already.
+    fn load(key: String, cx: &mut App) -> Task<()> {
+        if cx.default_global::<Self>().0.contains_key(&key) {
+            return Task::ready(());
+        }
+        let db_key = format!("{COMMAND_HISTORY_KEY_PREFIX}{key}");
+        let read = cx.background_spawn(async move { KEY_VALUE_STORE.read_kvp(&db_key) });
+        cx.spawn(async move |cx| {
+            let history = read
+                .await
+                .log_err()
+                .flatten()
+                .and_then(|history| serde_json::from_str::<CommandHistory>(&history).log_err())
+                .unwrap_or_default();
+            cx.update(|cx| {
+                cx.default_global::<Self>().0.entry(key).or_insert(history);
+            })
+            .log_err();
+        })
+    }
+
+    fn record(key: String, command: String, cx: &mut App) {
+        let load = Self::load(key.clone(), cx);
+        cx.spawn(async move |cx| {
+            load.await;
+            let history = cx.update(|cx| {
+                let history_size = LspWorkspaceCommandSettings::get_global(cx).history_size;
+                let history = cx
+                    .default_global::<Self>()
+                    .0
+                    .entry(key.clone())
+                    .or_default();
+                history.recent.record(&command, history_size);
+                history.usage.record(&command);
+                serde_json::to_string(history)
+            })??;
+            KEY_VALUE_STORE
+                .write_kvp(format!("{COMMAND_HISTORY_KEY_PREFIX}{key}"), history)
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Forgets the history of the workspace, keeping an empty one in its place so that it
+    /// isn't loaded again from the database before the deletion lands.
+    fn clear(key: String, cx: &mut App) {
+        let db_key = format!("{COMMAND_HISTORY_KEY_PREFIX}{key}");
+        cx.default_global::<Self>()
+            .0
+            .insert(key, CommandHistory::default());
+        cx.background_spawn(async move { KEY_VALUE_STORE.delete_kvp(db_key).await })
+            .detach_and_log_err(cx);
+    }
+}
+
+/// The ids of the most recently run commands, most recent first.
+#[derive(Default, Serialize, Deserialize)]
+struct RecentCommands(VecDeque<String>);
+
+impl RecentCommands {
+    /// Moves the command to the front, forgetting the oldest commands beyond `history_size`.
+    fn record(&mut self, command: &str, history_size: usize) {
+        self.0.retain(|recent| recent != command);
+        self.0.push_front(command.to_string());
+        self.0.truncate(history_size);
+    }
+}
+
+/// How many times each command id was run, and when it was last run.
+#[derive(Default, Serialize, Deserialize)]
+struct CommandUsage(HashMap<String, (u32, SystemTime)>);
+
+impl CommandUsage {
+    fn record(&mut self, command: &str) {
+        let now = SystemTime::now();
+        let (count, last_used) = self.0.entry(command.to_string()).or_insert((0, now));
+        *count += 1;
+        *last_used = now;
+    }
+
+    /// The ids of the commands that were run, most often run first and then most recently run.
+    fn most_used(&self) -> Vec<String> {
+        let mut usage = self.0.iter().collect::<Vec<_>>();
+        usage.sort_by(|(a, (a_count, a_used)), (b, (b_count, b_used))| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| b_used.cmp(a_used))
+                .then_with(|| a.cmp(b))
+        });
+        usage
+            .into_iter()
+            .map(|(command, _)| command.clone())
+            .collect()
+    }
+
+    /// Grows with the number of runs and decays with the hours since the last one.
+    fn frecency(&self, command: &str, now: SystemTime) -> f64 {
+        let Some((count, last_used)) = self.0.get(command) else {
+            return 0.0;
+        };
+        let hours_since_use = now
+            .duration_since(*last_used)
+            .unwrap_or_default()
+            .as_secs_f64()
+            / 3600.;
+        f64::from(*count).ln_1p() / (1. + hours_since_use)
+    }
+}
+
+/// Lists the commands that the language servers of `buffer` can execute, along with the server
+/// that offers each of them, honoring the `lsp_workspace_commands` settings.
+pub fn available_commands(
+    buffer: Entity<Buffer>,
+    lsp_store: &Entity<LspStore>,
+    cx: &mut App,
+) -> Task<anyhow::Result<Vec<(String, LanguageServerId)>>> {
+    let discovery = discover_commands(vec![buffer], lsp_store, cx);
+    cx.background_spawn(async move {
+        let (commands, _) = discovery.await?;
+        Ok(commands
+            .into_iter()
+            .map(|command| (command.command, command.server_id))
+            .collect())
+    })
+}
+
+/// Collects the commands that the buffers' language servers can execute, sorted by label, along
+/// with the names of those servers. In remote projects, the host lists the commands.
+///
+/// A command offered by the same server for several buffers runs against the first of them.
+fn discover_commands(
+    buffers: Vec<Entity<Buffer>>,
+    lsp_store: &Entity<LspStore>,
+    cx: &mut App,
+) -> Task<
+    anyhow::Result<(
+        Vec<WorkspaceCommand>,
+        HashMap<LanguageServerId, SharedString>,
+    )>,
+> {
+    let settings = LspWorkspaceCommandSettings::get_global(cx).clone();
+    let generation = cx.default_global::<CommandCache>().generation;
+    let requests = buffers
+        .into_iter()
+        .map(|buffer| {
+            let key = (lsp_store.entity_id(), buffer.read(cx).remote_id());
+            let cached_servers = cx
+                .default_global::<CommandCache>()
+                .servers
+                .get(&key)
+                .cloned();
+            lsp_store.update(cx, |lsp_store, cx| {
+                let titles = lsp_store.cached_code_lens_command_titles(key.1);
+                let servers = match cached_servers {
+                    Some(servers) => Task::ready(Ok(servers)),
+                    None => lsp_store.workspace_commands(&buffer, cx),
+                };
+                (key, buffer, titles, servers)
+            })
+        })
+        .collect::<Vec<_>>();
+    cx.spawn(async move |cx| {
+        let mut commands = Vec::new();
+        let mut server_names = HashMap::default();
+        for (key, buffer, mut titles, servers) in requests {
+            let servers = servers.await?;
+            cx.update(|cx| {
+                let cache = cx.default_global::<CommandCache>();
+                // Servers changed while these were requested, so they may be outdated.
+                if cache.generation == generation {
+                    cache.servers.insert(key, servers.clone());
+                }
+            })?;
+            for server in servers {
+                if settings.primary_server_only && !server.primary {
+                    continue;
+                }
+                server_names.insert(server.server_id, server.server_name.0.clone());
+                for command in server.commands {
+                    if !settings.is_listed(&server.server_name.0, &command) {
+                        continue;
+                    }
+                    commands.push(WorkspaceCommand {
+                        server_id: server.server_id,
+                        server_name: server.server_name.clone(),
+                        buffer: buffer.clone(),
+                        title: titles.remove(&(server.server_id, command.clone())),
+                        label: format!("{}: {}", server.server_name, command),
+                        aliases: settings.aliases_of(&command),
+                        root_path: server.root_path.clone(),
+                        provider_ids: vec![server.server_id],
+                        command,
+                    });
+                }
+            }
+        }
+
+        commands.sort_by(|a, b| {
+            a.label
+                .cmp(&b.label)
+                .then_with(|| a.server_id.cmp(&b.server_id))
+        });
+        commands.dedup_by(|a, b| a.server_id == b.server_id && a.command == b.command);
+        Ok((commands, server_names))
+    })
+}
+
+/// Offers the discovered commands of the buffer under the cursor in the command palette, each
+/// running like [`RunByName`].
+fn palette_commands(window: &Window, cx: &App) -> Vec<CommandPaletteCommand> {
+    let Some(Some(workspace)) = window.root::<Workspace>() else {
+        return Vec::new();
+    };
+    let workspace = workspace.read(cx);
+    let Some((_, buffer)) = LspWorkspaceCommandSelector::active_editor_and_buffer(workspace, cx)
+    else {
+        return Vec::new();
+    };
+    let key = (
+        workspace.project().read(cx).lsp_store().entity_id(),
+        buffer.read(cx).remote_id(),
+    );
+    // Macros are offered before the buffer's commands have been discovered, as they're
+    // only resolved to servers once they run.
+    let servers = cx
+        .try_global::<CommandCache>()
+        .and_then(|cache| cache.servers.get(&key))
+        .map_or(&[][..], |servers| servers.as_slice());
+    let settings = LspWorkspaceCommandSettings::get_global(cx);
+    let mut macro_names = settings.command_macros.keys().collect::<Vec<_>>();
+    macro_names.sort();
+    let macros = macro_names.into_iter().map(|name| CommandPaletteCommand {
+        name: format!("LSP Macro: {name}"),
+        action: Box::new(RunMacro { name: name.clone() }),
+    });
+    servers
+        .iter()
+        .filter(|server| server.primary || !settings.primary_server_only)
+        .flat_map(|server| {
+            server
+                .commands
+                .iter()
+                .filter(|command| settings.is_listed(&server.server_name.0, command))
+                .map(|command| CommandPaletteCommand {
+                    name: format!("LSP: {}: {command}", server.server_name),
+                    action: Box::new(RunByName {
+                        command: command.clone(),
+                        arguments: Vec::new(),
+                        server: None,
+                    }),
+                })
+        })
+        .chain(macros)
+        .collect()
+}
+
+/// A keymap entry binding a placeholder key to run the command on the named server, to be
+/// pasted into the keymap file.
+fn keybinding_snippet(command: &str, server_name: &str) -> String {
+    let entry = serde_json::json!({
+        "context": "Editor",
+        "bindings": {
+            "secondary-alt-r": [
+                RunByName::name_for_type(),
+                { "command": command, "server": server_name },
+            ],
+        },
+    });
+    serde_json::to_string_pretty(&entry).unwrap_or_default()
+}
+
+/// Runs the tasks marked as `lsp_command` like [`RunByName`], so that commands can be defined in
+/// tasks files and run from the task runner.
+struct TaskCommandProvider;
+
+impl workspace::LspCommandProvider for TaskCommandProvider {
+    fn execute_command(
+        &self,
+        workspace: &mut Workspace,
+        command: String,
+        arguments: Vec<String>,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let arguments = arguments.into_iter().map(task_argument).collect();
+        LspWorkspaceCommandSelector::run_by_name(workspace, command, arguments, None, window, cx);
+    }
+}
+
+/// Task arguments are strings, so the ones that are valid JSON are sent as the values they
+/// spell, letting tasks pass numbers, booleans and objects to commands.
+fn task_argument(argument: String) -> Value {
+    serde_json::from_str(&argument).unwrap_or(Value::String(argument))
+}
+
+/// The commands that the servers of each buffer reported, reused when the selector opens again
+/// until the servers change.
+#[derive(Default)]
+struct CommandCache {
+    generation: usize,
+    servers: HashMap<(EntityId, BufferId), Vec<LanguageServerCommands>>,
+}
+
+impl Global for CommandCache {}
+
+impl CommandCache {
+    fn clear(cx: &mut App) {
+        let cache = cx.default_global::<Self>();
+        cache.generation += 1;
+        cache.servers.clear();
+    }
+}
+
+/// Commands that are still running, kept so that they can be cancelled from their toast.
+#[derive(Default)]
+struct RunningCommands {
+    next_run_id: usize,
+    tasks: HashMap<usize, Task<()>>,
+}
+
+impl Global for RunningCommands {}
+
+/// Runs the command like [`run_command`], once the user confirms it for commands that are
+/// configured to require confirmation.
+fn confirm_and_run_command(
+    workspace: WeakEntity<Workspace>,
+    editor: &Entity<Editor>,
+    lsp_store: &Entity<LspStore>,
+    server_ids: Vec<LanguageServerId>,
+    command: String,
+    arguments: Vec<Value>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    if is_dry_run(cx) {
+        show_dry_run(
+            workspace,
+            vec![DryRunCommand {
+                command,
+                server_ids,
+                arguments: Ok(arguments),
+            }],
+            cx,
+        );
+        return;
+    }
+    let command_editor = CommandEditor {
+        editor: editor.downgrade(),
+        window: window.window_handle(),
+    };
+    if !LspWorkspaceCommandSettings::get_global(cx).requires_confirmation(&command) {
+        run_command(
+            workspace,
+            command_editor,
+            lsp_store,
+            server_ids,
+            command,
+            arguments,
+            None,
+            cx,
+        );
+        return;
+    }
+    let answer = window.prompt(
+        PromptLevel::Warning,
+        &format!("Run {command}?"),
+        Some("This command is configured to require confirmation, as it may be destructive."),
+        &["Run", "Cancel"],
+        cx,
+    );
+    let lsp_store = lsp_store.clone();
+    cx.spawn(async move |cx| {
+        if answer.await == Ok(0) {
+            cx.update(|cx| {
+                run_command(
+                    workspace,
+                    command_editor,
+                    &lsp_store,
+                    server_ids,
+                    command,
+                    arguments,
+                    None,
+                    cx,
+                );
+            })
+            .log_err();
+        }
+    })
+    .detach();
+}
+
+/// One of the servers that provide a command.
+struct CommandServer {
+    server_id: LanguageServerId,
+    server_name: SharedString,
+    root_path: Option<Arc<Path>>,
+}
+
+/// Asks which of the servers to run `command` on when they're rooted at different folders, as in
+/// a project with several worktrees, and resolves to all of them otherwise. Resolves to `None`
+/// if the prompt is cancelled.
+fn choose_servers(
+    command: &str,
+    servers: Vec<CommandServer>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<Option<Vec<LanguageServerId>>> {
+    let server_ids = servers
+        .iter()
+        .map(|server| server.server_id)
+        .collect::<Vec<_>>();
+    let Some(choices) = server_choices(&servers) else {
+        return Task::ready(Some(server_ids));
+    };
+    let answers = choices
+        .iter()
+        .map(String::as_str)
+        .chain(["All of Them", "Cancel"])
+        .collect::<Vec<_>>();
+    let answer = window.prompt(
+        PromptLevel::Info,
+        &format!("Run {command} on which server?"),
+        Some("Several servers provide this command, rooted at different folders."),
+        &answers,
+        cx,
+    );
+    cx.background_spawn(async move {
+        match answer.await.ok()? {
+            ix if ix < server_ids.len() => Some(vec![server_ids[ix]]),
+            ix if ix == server_ids.len() => Some(server_ids),
+            _ => None,
+        }
+    })
+}
+
+/// The choices to disambiguate the servers by, naming each server along with its root, or `None`
+/// if they're all rooted at the same folder.
+fn server_choices(servers: &[CommandServer]) -> Option<Vec<String>> {
+    let roots = servers
+        .iter()
+        .filter_map(|server| server.root_path.as_deref())
+        .collect::<HashSet<_>>();
+    if roots.len() < 2 {
+        return None;
+    }
+    Some(
+        servers
+            .iter()
+            .map(|server| match &server.root_path {
+                Some(root_path) => format!("{} in {}", server.server_name, root_path.display()),
+                None => server.server_name.to_string(),
+            })
+            .collect(),
+    )
+}
+
+/// A command queued in the selector or by a macro, to run after the ones queued before it.
+struct QueuedCommand {
+    buffer: Entity<Buffer>,
+    command: String,
+    server_ids: Vec<LanguageServerId>,
+    /// The unresolved arguments, whose templates are resolved once the command's turn comes.
+    input: String,
+}
+
+/// Runs the commands one after another and reports how many of them failed once they've all
+/// finished, or once one has failed with `stop_on_failure`. Cancelling one of them cancels the
+/// rest.
+fn run_command_queue(
+    workspace: WeakEntity<Workspace>,
+    editor: &Entity<Editor>,
+    lsp_store: &Entity<LspStore>,
+    queue: Vec<QueuedCommand>,
+    open_files: Vec<String>,
+    macro_name: Option<String>,
+    stop_on_failure: bool,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    struct CommandQueueToast;
+
+    if is_dry_run(cx) {
+        let commands = queue
+            .into_iter()
+            .map(|queued| DryRunCommand {
+                arguments: resolve_command_arguments(
+                    &queued.input,
+                    &queued.command,
+                    &queued.buffer,
+                    &open_files,
+                    editor,
+                    cx,
+                ),
+                command: queued.command,
+                server_ids: queued.server_ids,
+            })
+            .collect();
+        show_dry_run(workspace, commands, cx);
+        return;
+    }
+    let command_editor = CommandEditor {
+        editor: editor.downgrade(),
+        window: window.window_handle(),
+    };
+    let settings = LspWorkspaceCommandSettings::get_global(cx);
+    let confirmed_commands = queue
+        .iter()
+        .filter(|queued| settings.requires_confirmation(&queued.command))
+        .map(|queued| queued.command.as_str())
+        .collect::<Vec<_>>();
+    let answer = (!confirmed_commands.is_empty()).then(|| {
+        window.prompt(
+            PromptLevel::Warning,
+            &format!("Run {}?", confirmed_commands.join(", ")),
+            Some("These commands are configured to require confirmation, as they may be destructive."),
+            &["Run", "Cancel"],
+            cx,
+        )
+    });
+    let editor = editor.clone();
+    let lsp_store = lsp_store.clone();
+    cx.spawn(async move |cx| {
+        if let Some(answer) = answer
+            && answer.await != Ok(0)
+        {
+            return;
+        }
+        let total = queue.len();
+        let mut ran = 0;
+        let mut failed = Vec::new();
+        let mut results = Vec::new();
+        let mut cancelled = false;
+        for queued in queue {
+            let (done_tx, done_rx) = oneshot::channel();
+            let started = cx
+                .update(|cx| {
+                    let arguments = resolve_command_arguments(
+                        &queued.input,
+                        &queued.command,
+                        &queued.buffer,
+                        &open_files,
+                        &editor,
+                        cx,
+                    )?;
+                    run_command(
+                        workspace.clone(),
+                        command_editor.clone(),
+                        &lsp_store,
+                        queued.server_ids,
+                        queued.command.clone(),
+                        arguments,
+                        Some(done_tx),
+                        cx,
+                    );
+                    anyhow::Ok(())
+                })
+                .and_then(|started| started);
+            ran += 1;
+            if let Err(error) = started {
+                log::error!(target: LOG_TARGET, "failed to run {}: {error:#}", queued.command);
+                results.push(CommandResult {
+                    command: queued.command.clone(),
+                    server_name: None,
+                    result: Err(format!("{error:#}").into()),
+                });
+                failed.push(queued.command);
+            } else {
+                match done_rx.await {
+                    Ok(command_results) => {
+                        let succeeded = command_results.iter().all(|result| result.result.is_ok());
+                        results.extend(command_results);
+                        if succeeded {
+                            continue;
+                        }
+                        failed.push(queued.command);
+                    }
+                    Err(_) => {
+                        cancelled = true;
+                        break;
+                    }
+                }
+            }
+            if stop_on_failure {
+                break;
+            }
+        }
+        let mut message = queue_result_message(ran, total, &failed, cancelled);
+        if let Some(macro_name) = macro_name {
+            message = format!("Macro {macro_name}: {message}");
+        }
+        workspace
+            .update(cx, |workspace, cx| {
+                if !failed.is_empty() {
+                    show_command_results(workspace, message, results, None, cx);
+                    return;
+                }
+                let toast = Toast::new(NotificationId::unique::<CommandQueueToast>(), message);
+                let toast = if cancelled { toast } else { toast.autohide() };
+                workspace.show_toast(toast, cx);
+            })
+            .log_err();
+    })
+    .detach();
+}
+
+/// Summarizes how a queue of commands went, naming the commands that failed.
+fn queue_result_message(ran: usize, total: usize, failed: &[String], cancelled: bool) -> String {
+    let mut message = if cancelled {
+        format!("Cancelled the queued commands after {ran} of {total}")
+    } else if ran < total {
+        format!("Stopped the queued commands after {ran} of {total}")
+    } else {
+        match total {
+            1 => "Ran 1 queued command".to_string(),
+            total => format!("Ran {total} queued commands"),
+        }
+    };
+    if !failed.is_empty() {
+        message.push_str(&format!(", {} failed: {}", failed.len(), failed.join(", ")));
+    }
+    message
+}
+
+/// The editor that a command was run from, next to which its edits are shown.
+#[derive(Clone)]
+struct CommandEditor {
+    editor: WeakEntity<Editor>,
+    window: AnyWindowHandle,
+}
+
+/// Runs the command on each of the servers and reports the progress and outcome in one toast,
+/// or in a list of the outcome on each server when it failed on some of several. A failure can
+/// be retried from either, which runs the command again on the servers it failed on. Once it has
+/// finished, the outcomes are sent to `done`, which is dropped instead when the command is
+/// cancelled.
+fn run_command(
+    workspace: WeakEntity<Workspace>,
+    command_editor: CommandEditor,
+    lsp_store: &Entity<LspStore>,
+    server_ids: Vec<LanguageServerId>,
+    command: String,
+    arguments: Vec<Value>,
+    done: Option<oneshot::Sender<Vec<CommandResult>>>,
+    cx: &mut App,
+) {
+    struct CommandToast;
+
+    if let Some(key) = workspace
+        .read_with(cx, |workspace, cx| history_key(workspace, cx))
+        .log_err()
+    {
+        CommandHistories::record(key, command.clone(), cx);
+    }
+    let run_id = post_inc(&mut cx.default_global::<RunningCommands>().next_run_id);
+    let toast_id = NotificationId::composite::<CommandToast>(run_id);
+    workspace
+        .update(cx, |workspace, cx| {
+            let toast = Toast::new(toast_id.clone(), format!("Running {command}…")).on_click(
+                "Cancel",
+                move |_, cx| {
+                    // Dropping the task drops the requests, which asks the servers to cancel them.
+                    cx.default_global::<RunningCommands>().tasks.remove(&run_id);
+                },
+            );
+            workspace.show_toast(toast, cx);
+        })
+        .log_err();
+
+    let settings = LspWorkspaceCommandSettings::get_global(cx);
+    let preview = settings.preview_edits;
+    let refresh = settings.refreshes_after(&command);
+    let detailed_telemetry = settings.detailed_telemetry;
+    let start = Instant::now();
+    let mut stopped_servers = Vec::new();
+    let executions = server_ids
+        .into_iter()
+        .map(|server_id| {
+            let known_name = lsp_store
+                .read(cx)
+                .language_server_statuses()
+                .find(|(id, _)| *id == server_id)
+                .map(|(_, status)| status.name.clone());
+            let server_name = known_name
+                .clone()
+                .map_or_else(|| server_id.to_string().into(), SharedString::from);
+            let execution =
+                if ServerStatus::of(lsp_store.read(cx), server_id) == ServerStatus::Stopped {
+                    stopped_servers.push(known_name);
+                    Task::ready(Err(anyhow!(
+                        "language server {server_name} is no longer running"
+                    )))
+                } else {
+                    execute_command_for_edits(
+                        lsp_store,
+                        server_id,
+                        server_name.clone(),
+                        command.clone(),
+                        arguments.clone(),
+                        preview,
+                        cx,
+                    )
+                };
+            async move { (server_id, server_name, execution.await) }
+        })
+        .collect::<Vec<_>>();
+    let restart_servers = (!stopped_servers.is_empty()).then(|| {
+        // The names of servers that are already gone aren't known anymore, so restart all of
+        // the buffer's servers then.
+        stopped_servers
+            .into_iter()
+            .map(|name| {
+                name.map(|name| LanguageServerSelector::Name(LanguageServerName(name.into())))
+            })
+            .collect::<Option<HashSet<_>>>()
+            .unwrap_or_default()
+    });
+    let restart_lsp_store = lsp_store.downgrade();
+    let lsp_store = lsp_store.clone();
+    let task = cx.spawn(async move |cx| {
+        let mut transaction = ProjectTransaction::default();
+        let mut results = Vec::new();
+        let mut command_results = Vec::new();
+        let mut failed_server_ids = Vec::new();
+        let mut returned_locations = None;
+        let mut previewed_edits = Vec::new();
+        for (server_id, server_name, result) in future::join_all(executions).await {
+            let result = result.and_then(|(server_transaction, response)| {
+                let mut edited_files = server_transaction.0.len();
+                cx.update(|cx| transaction.merge(server_transaction, cx))?;
+                let response = match response
+                    .map(|response| workspace_edit_from_response(response.clone()).ok_or(response))
+                {
+                    Some(Ok(edit)) if preview => {
+                        edited_files += edited_file_count(&edit);
+                        previewed_edits.push(edit);
+                        None
+                    }
+                    Some(Ok(_)) => None,
+                    Some(Err(response)) => Some(response),
+                    None => None,
+                };
+                if returned_locations.is_none()
+                    && let Some(locations) = response.and_then(locations_from_response)
+                {
+                    returned_locations = Some((server_id, locations));
+                }
+                Ok(edited_files)
+            });
+            let shared_result = match &result {
+                Ok(edited_files) => Ok(*edited_files),
+                Err(error) => {
+                    failed_server_ids.push(server_id);
+                    Err(SharedString::from(command_error_message(error)))
+                }
+            };
+            command_results.push(CommandResult {
+                command: command.clone(),
+                server_name: Some(server_name.clone()),
+                result: shared_result.clone(),
+            });
+            let event = CommandEvent::CommandExecuted {
+                command: command.clone(),
+                server_id,
+                result: shared_result.clone(),
+            };
+            // Sent only with metrics enabled in the telemetry settings. Arguments are never sent.
+            telemetry::event!(
+                "LSP Workspace Command Executed",
+                server = server_name.to_string(),
+                succeeded = result.is_ok(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                command = detailed_telemetry.then(|| command.clone()),
+            );
+            cx.update(|cx| {
+                LastCommandStatuses::record(server_id, &command, &shared_result, cx);
+                if let Some(command_events) = CommandEvents::try_global(cx) {
+                    command_events.update(cx, |command_events, cx| command_events.emit(event, cx));
+                }
+            })
+            .log_err();
+            results.push((server_name, result));
+        }
+        let succeeded = results.iter().all(|(_, result)| result.is_ok());
+        let message = command_result_message(&command, results, start.elapsed());
+        // The restart button of the toast is more useful than the list when servers stopped.
+        let report = (!succeeded && command_results.len() > 1 && restart_servers.is_none())
+            .then(|| command_results.clone());
+        let retry = (!failed_server_ids.is_empty()).then(|| {
+            let workspace = workspace.clone();
+            let command_editor = command_editor.clone();
+            let lsp_store = lsp_store.clone();
+            let command = command.clone();
+            Arc::new(move |cx: &mut App| {
+                run_command(
+                    workspace.clone(),
+                    command_editor.clone(),
+                    &lsp_store,
+                    failed_server_ids.clone(),
+                    command.clone(),
+                    arguments.clone(),
+                    None,
+                    cx,
+                );
+            }) as Arc<dyn Fn(&mut App)>
+        });
+        workspace
+            .update(cx, |workspace, cx| {
+                if let Some(report) = report {
+                    let failures = report
+                        .iter()
+                        .filter(|result| result.result.is_err())
+                        .count();
+                    let summary =
+                        format!("{command} failed on {failures} of {} servers", report.len());
+                    workspace.dismiss_toast(&toast_id, cx);
+                    show_command_results(workspace, summary, report, retry, cx);
+                    return;
+                }
+                let toast = Toast::new(toast_id, message);
+                let toast = match restart_servers {
+                    Some(restart_servers) => {
+                        let editor = command_editor.editor.clone();
+                        toast.on_click("Restart Server", move |_, cx| {
+                            restart_language_servers(
+                                &restart_lsp_store,
+                                &editor,
+                                restart_servers.clone(),
+                                cx,
+                            );
+                        })
+                    }
+                    None if succeeded => toast.autohide(),
+                    None => match retry {
+                        Some(retry) => toast.on_click("Retry", move |_, cx| retry(cx)),
+                        None => toast,
+                    },
+                };
+                workspace.show_toast(toast, cx);
+            })
+            .log_err();
+        if !previewed_edits.is_empty()
+            && let Some(preview) = workspace
+                .read_with(cx, |workspace, _| workspace.project().clone())
+                .and_then(|project| {
+                    cx.update_window(command_editor.window, |_, window, cx| {
+                        preview_workspace_edits(
+                            workspace.clone(),
+                            project,
+                            command.clone(),
+                            previewed_edits,
+                            window,
+                            cx,
+                        )
+                    })
+                })
+                .log_err()
+        {
+            match preview.await {
+                Ok(Some(applied)) => {
+                    cx.update(|cx| transaction.merge(applied, cx)).log_err();
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    workspace
+                        .update(cx, |workspace, cx| {
+                            workspace.show_error(
+                                &error.context(format!("couldn't preview the edits of {command}")),
+                                cx,
+                            );
+                        })
+                        .log_err();
+                }
+            }
+        }
+        if refresh && succeeded {
+            refresh_hints_and_diagnostics(&lsp_store, &command_editor.editor, &transaction, cx);
+        }
+        if let Some((server_id, locations)) = returned_locations {
+            cx.update_window(command_editor.window, |_, window, cx| {
+                open_locations(
+                    workspace.clone(),
+                    &lsp_store,
+                    server_id,
+                    &command,
+                    locations,
+                    window,
+                    cx,
+                )
+                .detach_and_log_err(cx);
+            })
+            .log_err();
+        }
+        if !transaction.0.is_empty() {
+            cx.update_window(command_editor.window, |_, window, cx| {
+                show_edits(
+                    command_editor.editor,
+                    workspace,
+                    command,
+                    transaction,
+                    window,
+                    cx,
+                )
+                .detach_and_log_err(cx);
+            })
+            .log_err();
+        }
+        if let Some(done) = done {
+            done.send(command_results).ok();
+        }
+        cx.update(|cx| {
+            cx.default_global::<RunningCommands>().tasks.remove(&run_id);
+        })
+        .log_err();
+    });
+    cx.default_global::<RunningCommands>()
+        .tasks
+        .insert(run_id, task);
+}
+
+/// Returns the locations that a command's result points to, for navigation commands such as one
+/// going to a generated file.
+fn locations_from_response(response: Value) -> Option<Vec<lsp::Location>> {
+    if let Ok(location) = serde_json::from_value::<lsp::Location>(response.clone()) {
+        return Some(vec![location]);
+    }
+    serde_json::from_value::<Vec<lsp::Location>>(response)
+        .ok()
+        .filter(|locations| !locations.is_empty())
+}
+
+/// Opens the locations that a command returned, selecting the range of a single location or
+/// listing several in a multibuffer.
+fn open_locations(
+    workspace: WeakEntity<Workspace>,
+    lsp_store: &Entity<LspStore>,
+    server_id: LanguageServerId,
+    command: &str,
+    locations: Vec<lsp::Location>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<anyhow::Result<()>> {
+    let Some(server_name) = lsp_store
+        .read(cx)
+        .language_server_statuses()
+        .find(|(id, _)| *id == server_id)
+        .map(|(_, status)| LanguageServerName(status.name.clone().into()))
+    else {
+        return Task::ready(Err(anyhow!(
+            "language server {server_id} is no longer running"
+        )));
+    };
+    let opened_buffers = locations
+        .into_iter()
+        .map(|location| {
+            let open_buffer = lsp_store.update(cx, |lsp_store, cx| {
+                lsp_store.open_local_buffer_via_lsp(
+                    location.uri,
+                    server_id,
+                    server_name.clone(),
+                    cx,
+                )
+            });
+            async move { anyhow::Ok((open_buffer.await?, location.range)) }
+        })
+        .collect::<Vec<_>>();
+    let title = format!("Locations of {command}");
+    window.spawn(cx, async move |cx| {
+        let opened_buffers = future::try_join_all(opened_buffers).await?;
+        workspace.update_in(cx, |workspace, window, cx| {
+            if let [(buffer, range)] = opened_buffers.as_slice() {
+                let snapshot = buffer.read(cx).snapshot();
+                let start = snapshot.point_utf16_to_offset(
+                    snapshot.clip_point_utf16(point_from_lsp(range.start), Bias::Left),
+                );
+                let end = snapshot.point_utf16_to_offset(
+                    snapshot.clip_point_utf16(point_from_lsp(range.end), Bias::Left),
+                );
+                let editor = workspace.open_project_item::<Editor>(
+                    workspace.active_pane().clone(),
+                    buffer.clone(),
+                    true,
+                    true,
+                    window,
+                    cx,
+                );
+                editor.update(cx, |editor, cx| {
+                    editor.change_selections(
+                        SelectionEffects::scroll(Autoscroll::center()),
+                        window,
+                        cx,
+                        |selections| selections.select_ranges([start..end]),
+                    );
+                });
+                return;
+            }
+            let locations = opened_buffers
+                .into_iter()
+                .map(|(buffer, range)| {
+                    let snapshot = buffer.read(cx).snapshot();
+                    let start = snapshot.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                    let end = snapshot.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                    Location {
+                        range: snapshot.anchor_before(start)..snapshot.anchor_after(end),
+                        buffer,
+                    }
+                })
+                .collect();
+            Editor::open_locations_in_multibuffer(
+                workspace,
+                locations,
+                title,
+                false,
+                MultibufferSelectionMode::First,
+                window,
+                cx,
+            );
+        })
+    })
+}
+
+/// Refreshes the inlay hints and diagnostics of the buffers that a command ran against and
+/// edited, which servers may otherwise leave stale after commands such as reloading the
+/// workspace.
+fn refresh_hints_and_diagnostics(
+    lsp_store: &Entity<LspStore>,
+    editor: &WeakEntity<Editor>,
+    transaction: &ProjectTransaction,
+    cx: &mut AsyncApp,
+) {
+    cx.update(|cx| {
+        let mut buffers = editor
+            .upgrade()
+            .map(|editor| editor.read(cx).buffer().read(cx).all_buffers())
+            .unwrap_or_default();
+        buffers.extend(transaction.0.keys().cloned());
+        lsp_store
+            .update(cx, |lsp_store, cx| {
+                lsp_store.refresh_hints_and_diagnostics(buffers.into_iter().collect(), cx)
+            })
+            .detach_and_log_err(cx);
+    })
+    .log_err();
+}
+
+/// Restarts the given servers of the buffers that `editor` shows, or all of their servers when
+/// none are given.
+fn restart_language_servers(
+    lsp_store: &WeakEntity<LspStore>,
+    editor: &WeakEntity<Editor>,
+    servers: HashSet<LanguageServerSelector>,
+    cx: &mut App,
+) {
+    let Some(editor) = editor.upgrade() else {
+        return;
+    };
+    let buffers = editor.read(cx).buffer().read(cx).all_buffers();
+    lsp_store
+        .update(cx, |lsp_store, cx| {
+            lsp_store.restart_language_servers_for_buffers(
+                buffers.into_iter().collect(),
+                servers,
+                cx,
+            )
+        })
+        .log_err();
+}
+
+/// How long to wait for restarted servers to provide the command that they were restarted for.
+const RESTART_TIMEOUT: Duration = Duration::from_secs(30);
+const RESTART_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Restarts the stopped servers to run `command` on them, after asking whether to with `ask`.
+/// Resolves to the restarted servers that provide the command once they're running, or to none
+/// when it's cancelled or they haven't started in time.
+fn restart_for_command(
+    workspace: WeakEntity<Workspace>,
+    lsp_store: Entity<LspStore>,
+    editor: Entity<Editor>,
+    buffers: Vec<Entity<Buffer>>,
+    command: String,
+    server_names: HashSet<LanguageServerName>,
+    ask: bool,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<Option<Vec<LanguageServerId>>> {
+    let mut names = server_names
+        .iter()
+        .map(|name| name.0.to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    let names = names.join(", ");
+    let answer = ask.then(|| {
+        window.prompt(
+            PromptLevel::Info,
+            &format!("Restart {names} to run {command}?"),
+            Some("The language server has stopped."),
+            &["Restart and Run", "Cancel"],
+            cx,
+        )
+    });
+    window.spawn(cx, async move |cx| {
+        if let Some(answer) = answer
+            && answer.await != Ok(0)
+        {
+            return None;
+        }
+        let selectors = server_names
+            .iter()
+            .map(|name| LanguageServerSelector::Name(name.clone()))
+            .collect();
+        cx.update(|_, cx| {
+            restart_language_servers(&lsp_store.downgrade(), &editor.downgrade(), selectors, cx);
+        })
+        .ok()?;
+        let start = Instant::now();
+        while start.elapsed() < RESTART_TIMEOUT {
+            cx.background_executor().timer(RESTART_POLL_INTERVAL).await;
+            let discovery = cx
+                .update(|_, cx| {
+                    CommandCache::clear(cx);
+                    discover_commands(buffers.clone(), &lsp_store, cx)
+                })
+                .ok()?;
+            let Some((commands, _)) = discovery.await.log_err() else {
+                continue;
+            };
+            let server_ids = lsp_store
+                .read_with(cx, |lsp_store, _| {
+                    let mut server_ids = commands
+                        .iter()
+                        .filter(|restarted| {
+                            restarted.command == command
+                                && server_names.contains(&restarted.server_name)
+                                && ServerStatus::of(lsp_store, restarted.server_id)
+                                    == ServerStatus::Running
+                        })
+                        .map(|restarted| restarted.server_id)
+                        .collect::<Vec<_>>();
+                    server_ids.sort();
+                    server_ids.dedup();
+                    server_ids
+                })
+                .ok()?;
+            if !server_ids.is_empty() {
+                return Some(server_ids);
+            }
+        }
+        workspace
+            .update(cx, |workspace, cx| {
+                LspWorkspaceCommandSelector::show_unavailable_toast(
+                    workspace,
+                    format!("{names} didn't start in time to run {command}"),
+                    cx,
+                );
+            })
+            .log_err();
+        None
+    })
+}
+
+/// Shows the edits of a command in a multibuffer, where they're undone together, unless they're
+/// all within the excerpt that the editor is showing.
+fn show_edits(
+    editor: WeakEntity<Editor>,
+    workspace: WeakEntity<Workspace>,
+    command: String,
+    transaction: ProjectTransaction,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<anyhow::Result<()>> {
+    let first_edit = editor
+        .upgrade()
+        .and_then(|editor| first_edit_in_editor(&editor, &transaction, cx));
+    window.spawn(cx, async move |cx| {
+        if let Some(first_edit) = first_edit {
+            editor.update_in(cx, |editor, window, cx| {
+                editor.change_selections(SelectionEffects::default(), window, cx, |selections| {
+                    selections.select_anchor_ranges([first_edit..first_edit])
+                });
+            })?;
+        }
+        Editor::open_project_transaction(
+            &editor,
+            workspace,
+            transaction,
+            format!("Edits of {command}"),
+            cx,
+        )
+        .await
+    })
+}
+
+/// Returns where the first of a command's edits to the buffer under the editor's cursor starts,
+/// for moving the cursor there.
+fn first_edit_in_editor(
+    editor: &Entity<Editor>,
+    transaction: &ProjectTransaction,
+    cx: &App,
+) -> Option<Anchor> {
+    let multi_buffer = editor.read(cx).buffer().read(cx);
+    let (excerpt_id, buffer, _) =
+        multi_buffer.excerpt_containing(editor.read(cx).selections.newest_anchor().head(), cx)?;
+    let buffer_transaction = transaction.0.get(&buffer)?;
+    let buffer = buffer.read(cx);
+    let start = buffer
+        .edited_ranges_for_transaction::<usize>(buffer_transaction)
+        .map(|range| range.start)
+        .min()?;
+    multi_buffer
+        .snapshot(cx)
+        .anchor_in_excerpt(excerpt_id, buffer.anchor_before(start))
+}
+
+/// Runs the command on the server and opens the value it returns in a new buffer, pretty-printed
+/// under a header that names the command and its arguments.
+fn inspect_command_result(
+    workspace: WeakEntity<Workspace>,
+    lsp_store: &Entity<LspStore>,
+    server_id: LanguageServerId,
+    command: String,
+    arguments: Vec<Value>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let request = execute_command(lsp_store, server_id, command.clone(), arguments.clone(), cx);
+    let Some(languages) = workspace
+        .read_with(cx, |workspace, cx| {
+            workspace.project().read(cx).languages().clone()
+        })
+        .log_err()
+    else {
+        return;
+    };
+    let error_message = format!("Failed to run {command}");
+    window
+        .spawn(cx, async move |cx| {
+            let result = request.await?;
+            let text =
+                command_result_text(&command, &arguments, (!result.is_null()).then_some(&result))?;
+            let json = languages.language_for_name("JSON").await.log_err();
+            workspace.update_in(cx, |workspace, window, cx| {
+                open_json_buffer(
+                    workspace,
+                    text,
+                    json,
+                    format!("{command} result"),
+                    window,
+                    cx,
+                );
+            })
+        })
+        .detach_and_prompt_err(&error_message, window, cx, |_, _, _| None);
+}
+
+/// Opens a new buffer with JSON text in the active pane.
+fn open_json_buffer(
+    workspace: &mut Workspace,
+    text: String,
+    json: Option<Arc<Language>>,
+    title: String,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let buffer = project.update(cx, |project, cx| {
+        project.create_local_buffer(&text, json, cx)
+    });
+    let buffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+    workspace.add_item_to_active_pane(
+        Box::new(cx.new(|cx| Editor::for_multibuffer(buffer, Some(project), window, cx))),
+        None,
+        true,
+        window,
+        cx,
+    );
+}
+
+/// Lists the commands that each server advertises as its `executeCommandProvider`, along with
+/// those of them that the settings leave out of the selector.
+fn capabilities_text(
+    servers: &[LanguageServerCommands],
+    settings: &LspWorkspaceCommandSettings,
+) -> serde_json::Result<String> {
+    let servers = servers
+        .iter()
+        .map(|server| {
+            let hidden_commands = server
+                .commands
+                .iter()
+                .filter(|command| !settings.is_listed(&server.server_name.0, command))
+                .collect::<Vec<_>>();
+            serde_json::json!({
+                "server": server.server_name.to_string(),
+                "id": server.server_id.0,
+                "executeCommandProvider": { "commands": server.commands },
+                "hiddenBySettings": hidden_commands,
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(format!(
+        "// The workspace commands advertised by the language servers of the active buffer.\n\
+         // Servers that don't advertise an executeCommandProvider are left out.\n{}\n",
+        serde_json::to_string_pretty(&servers)?
+    ))
+}
+
+/// The text of the buffer that shows a command's result.
+fn command_result_text(
+    command: &str,
+    arguments: &[Value],
+    result: Option<&Value>,
+) -> serde_json::Result<String> {
+    Ok(format!(
+        "// Result of {command}\n// Arguments: {}\n{}\n",
+        serde_json::to_string(arguments)?,
+        serde_json::to_string_pretty(&result)?
+    ))
+}
+
+/// Tells apart the servers that share a name, such as the instances of one server for several
+/// worktrees, by the name of the worktree that each one runs for or, when that doesn't tell them
+/// apart either, by their ids.
+fn disambiguate_server_names(
+    server_names: &mut HashMap<LanguageServerId, SharedString>,
+    worktree_names: &HashMap<LanguageServerId, SharedString>,
+) {
+    let mut servers_by_name = HashMap::<SharedString, Vec<LanguageServerId>>::default();
+    for (server_id, server_name) in server_names.iter() {
+        servers_by_name
+            .entry(server_name.clone())
+            .or_default()
+            .push(*server_id);
+    }
+    for (server_name, server_ids) in servers_by_name {
+        if server_ids.len() < 2 {
+            continue;
+        }
+        let worktrees = server_ids
+            .iter()
+            .map(|server_id| worktree_names.get(server_id))
+            .collect::<Option<HashSet<_>>>();
+        let by_worktree = worktrees.is_some_and(|worktrees| worktrees.len() == server_ids.len());
+        for server_id in server_ids {
+            let disambiguator = match worktree_names.get(&server_id) {
+                Some(worktree_name) if by_worktree => worktree_name.to_string(),
+                _ => server_id.to_string(),
+            };
+            server_names.insert(server_id, format!("{server_name} ({disambiguator})").into());
+        }
+    }
+}
+
+/// The index of the first item with the same key as each item, which is its own index unless
+/// it duplicates an earlier one.
+fn duplicate_primaries<K: Eq + std::hash::Hash>(keys: impl IntoIterator<Item = K>) -> Vec<usize> {
+    let mut primaries = HashMap::default();
+    keys.into_iter()
+        .enumerate()
+        .map(|(ix, key)| *primaries.entry(key).or_insert(ix))
+        .collect()
+}
+
+/// Splits a query like `eslint>fix` or `@eslint fix` into the server prefix and the rest.
+fn split_server_prefix(query: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = match query.strip_prefix('@') {
+        Some(query) => query.split_once(char::is_whitespace)?,
+        None => query.split_once('>')?,
+    };
+    let prefix = prefix.trim();
+    (!prefix.is_empty()).then(|| (prefix, rest.trim_start()))
+}
+
+/// Keeps the candidates that matched every term of a query, scoring them by the sum of their
+/// scores for the terms and highlighting the positions of all of the terms.
+fn intersect_term_matches(term_matches: Vec<Vec<StringMatch>>) -> Vec<StringMatch> {
+    let mut term_matches = term_matches.into_iter();
+    let Some(first_matches) = term_matches.next() else {
+        return Vec::new();
+    };
+    let mut matches = first_matches
+        .into_iter()
+        .map(|mat| (mat.candidate_id, mat))
+        .collect::<HashMap<_, _>>();
+    for term_matches in term_matches {
+        let mut term_matches = term_matches
+            .into_iter()
+            .map(|mat| (mat.candidate_id, mat))
+            .collect::<HashMap<_, _>>();
+        matches.retain(
+            |candidate_id, mat| match term_matches.remove(candidate_id) {
+                Some(term_match) => {
+                    mat.score += term_match.score;
+                    mat.positions.extend(term_match.positions);
+                    true
+                }
+                None => false,
+            },
+        );
+    }
+    let mut matches = matches
+        .into_values()
+        .map(|mut mat| {
+            mat.positions.sort_unstable();
+            mat.positions.dedup();
+            mat
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.string.cmp(&b.string))
+    });
+    matches
+}
+
+/// Matches the candidates whose command id contains `literal`, for a query starting with `=`.
+/// Each candidate comes with its command id and where that starts in the candidate's text, so
+/// that's where the literal is highlighted.
+fn substring_matches<'a>(
+    candidates: impl IntoIterator<Item = (&'a StringMatchCandidate, &'a str, usize)>,
+    literal: &str,
+    case_sensitive: bool,
+) -> Vec<StringMatch> {
+    let literal = if case_sensitive {
+        literal.to_string()
+    } else {
+        literal.to_ascii_lowercase()
+    };
+    candidates
+        .into_iter()
+        .filter_map(|(candidate, command, command_start)| {
+            let start = if case_sensitive {
+                command.find(&literal)?
+            } else {
+                command.to_ascii_lowercase().find(&literal)?
+            };
+            let offset = command_start + start;
+            Some(StringMatch {
+                candidate_id: candidate.id,
+                string: candidate.string.clone(),
+                positions: (offset..offset + literal.len())
+                    .filter(|ix| candidate.string.is_char_boundary(*ix))
+                    .collect(),
+                score: 0.0,
+            })
+        })
+        .collect()
+}
+
+/// Names a command along with its id and server, for the tooltip of its row and the description
+/// of the selection, as its row may only show the title highlighted.
+fn command_description(title: Option<&str>, command: &str, server_name: &str) -> String {
+    match title {
+        Some(title) => format!("{title} ({command}) on {server_name}"),
+        None => format!("{command} on {server_name}"),
+    }
+}
+
+/// Returns the matched positions that fall within one segment of the matched text, relative to
+/// the start of that segment, for rendering the segment in a label of its own.
+///
+/// The positions are byte offsets, so those that don't start a character of the segment are
+/// left out, as a label can't highlight from the middle of a multibyte character.
+fn segment_positions(positions: &[usize], start: usize, segment: &str) -> Vec<usize> {
+    positions
+        .iter()
+        .filter_map(|position| position.checked_sub(start))
+        .filter(|position| *position < segment.len() && segment.is_char_boundary(*position))
+        .collect()
+}
+
+/// Summarizes the outcome of running a command, given the number of files each server edited
+/// and how long the servers took.
+fn command_result_message(
+    command: &str,
+    results: Vec<(SharedString, anyhow::Result<usize>)>,
+    elapsed: Duration,
+) -> String {
+    let server_count = results.len();
+    let mut edited_files = 0;
+    let mut failures = Vec::new();
+    for (server_name, result) in results {
+        match result {
+            Ok(files) => edited_files += files,
+            Err(error) if server_count == 1 => {
+                return format!("{command} failed: {}", command_error_message(&error));
+            }
+            Err(error) => {
+                failures.push(format!("{server_name}: {}", command_error_message(&error)))
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        return format!(
+            "{command} failed on {} of {server_count} servers. {}",
+            failures.len(),
+            failures.join("; ")
+        );
+    }
+    let mut message = format!("Ran {command}");
+    if server_count > 1 {
+        message.push_str(&format!(" on {server_count} servers"));
+    }
+    message.push_str(&format!(" in {:.1}s", elapsed.as_secs_f64()));
+    match edited_files {
+        0 => {}
+        1 => message.push_str(", which edited 1 file"),
+        edited_files => message.push_str(&format!(", which edited {edited_files} files")),
+    }
+    message
+}
+
+/// Describes why a command failed, telling an error that the server responded with apart from
+/// the request failing to be sent or answered, which is reported as it is.
+fn command_error_message(error: &anyhow::Error) -> String {
+    let Some(response_error) = error
+        .chain()
+        .find_map(|error| error.downcast_ref::<lsp::ResponseError>())
+    else {
+        return format!("{error:#}");
+    };
+    let message = &response_error.message;
+    match response_error.code {
+        Some(code) => match response_error_name(code) {
+            Some(name) => format!("the server returned error {code} ({name}): {message}"),
+            None => format!("the server returned error {code}: {message}"),
+        },
+        None => format!("the server returned an error: {message}"),
+    }
+}
+
+/// The names of the error codes that JSON-RPC and the LSP specification define.
+fn response_error_name(code: i64) -> Option<&'static str> {
+    Some(match code {
+        -32700 => "parse error",
+        -32600 => "invalid request",
+        -32601 => "method not found",
+        -32602 => "invalid params",
+        -32603 => "internal error",
+        -32002 => "server not initialized",
+        -32800 => "request cancelled",
+        -32801 => "content modified",
+        -32802 => "server cancelled",
+        -32803 => "request failed",
+        _ => return None,
+    })
+}
+
+/// Executes `command` on the given language server and returns the value that it responds with,
+/// which is null when it responds without one. Unlike running the command from the selector,
+/// the edits within that value aren't applied.
+pub fn execute_command(
+    lsp_store: &Entity<LspStore>,
+    server_id: LanguageServerId,
+    command: String,
+    arguments: Vec<Value>,
+    cx: &mut App,
+) -> Task<anyhow::Result<Value>> {
+    let timeout = Duration::from_millis(LspWorkspaceCommandSettings::get_global(cx).timeout_ms);
+    let timeout = (!timeout.is_zero()).then_some(timeout);
+    log::info!(
+        target: LOG_TARGET,
+        "running {command} on {server_id} for its result, with arguments {}",
+        Value::from(arguments.clone())
+    );
+    let request = lsp_store.update(cx, |lsp_store, cx| {
+        lsp_store.workspace_command_result(server_id, command.clone(), arguments, timeout, cx)
+    });
+    cx.background_spawn(async move {
+        let start = Instant::now();
+        let result = request.await;
+        let elapsed = start.elapsed();
+        match &result {
+            Ok(_) => log::info!(target: LOG_TARGET, "ran {command} in {elapsed:?}"),
+            Err(error) => log::error!(
+                target: LOG_TARGET,
+                "{command} failed after {elapsed:?}: {error:#}"
+            ),
+        }
+        Ok(result?.unwrap_or(Value::Null))
+    })
+}
+
+/// Runs the command and returns the edits it made along with its result, saving the files that
+/// were opened only to apply them. With `preview`, an edit that the command returns is left in
+/// its result to be previewed rather than applied, while the edits that the server applies
+/// itself as it runs the command are applied right away.
+fn execute_command_for_edits(
+    lsp_store: &Entity<LspStore>,
+    server_id: LanguageServerId,
+    server_name: SharedString,
+    command: String,
+    arguments: Vec<Value>,
+    preview: bool,
+    cx: &mut App,
+) -> Task<anyhow::Result<(ProjectTransaction, Option<Value>)>> {
+    let timeout = Duration::from_millis(LspWorkspaceCommandSettings::get_global(cx).timeout_ms);
+    let timeout = (!timeout.is_zero()).then_some(timeout);
+    let buffer_store = lsp_store.read(cx).buffer_store();
+    let open_buffers = buffer_store
+        .read(cx)
+        .buffers()
+        .map(|buffer| buffer.read(cx).remote_id())
+        .collect::<HashSet<_>>();
+    log::info!(
+        target: LOG_TARGET,
+        "running {command} on {server_name} with arguments {}",
+        Value::from(arguments.clone())
+    );
+    let execution = lsp_store.update(cx, |lsp_store, cx| {
+        lsp_store.execute_workspace_command(
+            server_id,
+            command.clone(),
+            arguments,
+            timeout,
+            !preview,
+            cx,
+        )
+    });
+    cx.spawn(async move |cx| {
+        let start = Instant::now();
+        let result = execution.await;
+        let elapsed = start.elapsed();
+        let (transaction, response) = match result {
+            Ok((transaction, response)) => {
+                log::info!(
+                    target: LOG_TARGET,
+                    "ran {command} on {server_name} in {elapsed:?}, editing {} files",
+                    transaction.0.len()
+                );
+                (transaction, response)
+            }
+            Err(error) => {
+                log::error!(
+                    target: LOG_TARGET,
+                    "{command} failed on {server_name} after {elapsed:?}: {error:#}"
+                );
+                let responded = error.chain().any(|error| error.is::<lsp::ResponseError>());
+                return Err(if responded {
+                    error
+                } else {
+                    error.context(format!("request to {server_name} failed"))
+                });
+            }
+        };
+        // The edits are shown along with the preview, which keeps their buffers open.
+        if preview {
+            return Ok((transaction, response));
+        }
+
+        // Buffers that were opened just to apply the edits aren't shown anywhere, so their
+        // changes would otherwise be lost when they're released.
+        let saves = buffer_store.update(cx, |buffer_store, cx| {
+            transaction
+                .0
+                .keys()
+                .filter(|buffer| !open_buffers.contains(&buffer.read(cx).remote_id()))
+                .map(|buffer| buffer_store.save_buffer(buffer.clone(), cx))
+                .collect::<Vec<_>>()
+        })?;
+        future::try_join_all(saves)
+            .await
+            .context("save files edited by lsp workspace command")?;
+        Ok((transaction, response))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_recent_commands() {
+        let default_settings =
+            settings::parse_json_with_comments::<Value>(&settings::default_settings()).unwrap();
+        let history_size = default_settings["lsp_workspace_commands"]["history_size"]
+            .as_u64()
+            .unwrap() as usize;
+        let mut recent_commands = RecentCommands::default();
+        for command in ["a", "b", "a"] {
+            recent_commands.record(command, history_size);
+        }
+        assert_eq!(recent_commands.0, ["a", "b"]);
+
+        for ix in 0..history_size {
+            recent_commands.record(&ix.to_string(), history_size);
+        }
+        assert_eq!(recent_commands.0.len(), history_size);
+        let last = (history_size - 1).to_string();
+        assert_eq!(recent_commands.0.front(), Some(&last));
+
+        recent_commands.record("a", 2);
+        assert_eq!(recent_commands.0, ["a", last.as_str()]);
+    }
+
+    #[test]
+    fn test_command_usage_frecency() {
+        let mut usage = CommandUsage::default();
+        usage.record("rare");
+        for _ in 0..5 {
+            usage.record("frequent");
+        }
+
+        let now = SystemTime::now();
+        assert_eq!(usage.frecency("unused", now), 0.);
+        assert!(usage.frecency("frequent", now) > usage.frecency("rare", now));
+
+        let later = now + Duration::from_secs(2 * 60 * 60);
+        assert!(usage.frecency("frequent", later) < usage.frecency("frequent", now));
+    }
+
+    #[test]
+    fn test_most_used_commands() {
+        let mut usage = CommandUsage::default();
+        for command in ["once", "twice", "later", "twice"] {
+            usage.record(command);
+        }
+        assert_eq!(usage.most_used(), ["twice", "later", "once"]);
+    }
+
+    #[test]
+    fn test_sort_order_keys() {
+        let mut order = SortOrder::default();
+        for _ in 0..3 {
+            assert_eq!(SortOrder::from_key(order.key()), Some(order));
+            order = order.next();
+        }
+        assert_eq!(order, SortOrder::default());
+        assert_eq!(SortOrder::from_key("unknown"), None);
+    }
+
+    #[test]
+    fn test_disambiguate_server_names() {
+        let id = LanguageServerId;
+        let mut server_names = HashMap::from_iter([
+            (id(0), SharedString::from("rust-analyzer")),
+            (id(1), SharedString::from("rust-analyzer")),
+            (id(2), SharedString::from("eslint")),
+            (id(3), SharedString::from("eslint")),
+            (id(4), SharedString::from("taplo")),
+        ]);
+        let worktree_names = HashMap::from_iter([
+            (id(0), SharedString::from("app")),
+            (id(1), SharedString::from("lib")),
+            (id(2), SharedString::from("web")),
+            (id(3), SharedString::from("web")),
+            (id(4), SharedString::from("app")),
+        ]);
+        disambiguate_server_names(&mut server_names, &worktree_names);
+        assert_eq!(server_names[&id(0)].to_string(), "rust-analyzer (app)");
+        assert_eq!(server_names[&id(1)].to_string(), "rust-analyzer (lib)");
+        assert_eq!(server_names[&id(2)].to_string(), "eslint (2)");
+        assert_eq!(server_names[&id(3)].to_string(), "eslint (3)");
+        assert_eq!(server_names[&id(4)].to_string(), "taplo");
+    }
+
+    #[test]
+    fn test_server_choices() {
+        let server = |id, server_name: &str, root_path: Option<&str>| CommandServer {
+            server_id: LanguageServerId(id),
+            server_name: server_name.to_string().into(),
+            root_path: root_path.map(|root_path| Arc::from(Path::new(root_path))),
+        };
+        assert_eq!(
+            server_choices(&[
+                server(0, "rust-analyzer", Some("/code/app")),
+                server(1, "rust-analyzer", Some("/code/lib")),
+                server(2, "rust-analyzer", None),
+            ]),
+            Some(vec![
+                "rust-analyzer in /code/app".to_string(),
+                "rust-analyzer in /code/lib".to_string(),
+                "rust-analyzer".to_string(),
+            ])
+        );
+        assert_eq!(
+            server_choices(&[
+                server(0, "rust-analyzer", Some("/code/app")),
+                server(1, "clippy", Some("/code/app")),
+            ]),
+            None
+        );
+        assert_eq!(server_choices(&[server(0, "taplo", None)]), None);
+    }
+
+    #[test]
+    fn test_task_argument() {
+        assert_eq!(task_argument("42".to_string()), json!(42));
+        assert_eq!(
+            task_argument("{\"all\": true}".to_string()),
+            json!({ "all": true })
+        );
+        assert_eq!(
+            task_argument("src/main.rs".to_string()),
+            json!("src/main.rs")
+        );
+        assert_eq!(task_argument("\"true\"".to_string()), json!("true"));
+    }
+
+    #[test]
+    fn test_keybinding_snippet() {
+        let snippet = keybinding_snippet("rust-analyzer.reloadWorkspace", "rust-analyzer");
+        assert_eq!(
+            serde_json::from_str::<Value>(&snippet).unwrap(),
+            json!({
+                "context": "Editor",
+                "bindings": {
+                    "secondary-alt-r": [
+                        "lsp_workspace_command_selector::RunByName",
+                        {
+                            "command": "rust-analyzer.reloadWorkspace",
+                            "server": "rust-analyzer",
+                        },
+                    ],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_duplicate_primaries() {
+        assert_eq!(
+            duplicate_primaries(["fix", "format", "fix", "restart", "format"]),
+            vec![0, 1, 0, 3, 1]
+        );
+        assert_eq!(duplicate_primaries(Vec::<&str>::new()), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_split_server_prefix() {
+        assert_eq!(split_server_prefix("eslint>fix"), Some(("eslint", "fix")));
+        assert_eq!(
+            split_server_prefix("ts> organize"),
+            Some(("ts", "organize"))
+        );
+        assert_eq!(
+            split_server_prefix("@rust-analyzer reload"),
+            Some(("rust-analyzer", "reload"))
+        );
+        assert_eq!(split_server_prefix("@rust-analyzer"), None);
+        assert_eq!(split_server_prefix(">fix"), None);
+        assert_eq!(split_server_prefix("organize imports"), None);
+    }
+
+    #[test]
+    fn test_intersect_term_matches() {
+        let mat =
+            |candidate_id: usize, string: &str, score: f64, positions: Vec<usize>| StringMatch {
+                candidate_id,
+                string: string.to_string(),
+                positions,
+                score,
+            };
+        let matches = intersect_term_matches(vec![
+            vec![
+                mat(0, "rust-analyzer: reload", 0.5, vec![0, 1]),
+                mat(1, "rust-analyzer: run", 0.9, vec![0, 1]),
+            ],
+            vec![
+                mat(2, "eslint: reload", 0.8, vec![8, 9]),
+                mat(0, "rust-analyzer: reload", 0.6, vec![15, 16, 1]),
+            ],
+        ]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].candidate_id, 0);
+        assert!((matches[0].score - 1.1).abs() < 1e-9);
+        assert_eq!(matches[0].positions, vec![0, 1, 15, 16]);
+        assert!(intersect_term_matches(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_substring_matches() {
+        let candidates = [
+            StringMatchCandidate::new(0, "eslint: eslint.applyAllFixes"),
+            StringMatchCandidate::new(1, "eslint: Restart eslint.restart"),
+            StringMatchCandidate::new(2, "rust-analyzer: rust-analyzer.reloadWorkspace"),
+        ];
+        let commands = [
+            ("eslint.applyAllFixes", 8),
+            ("eslint.restart", 16),
+            ("rust-analyzer.reloadWorkspace", 15),
+        ];
+        let pairs = || {
+            candidates
+                .iter()
+                .zip(commands)
+                .map(|(candidate, (command, command_start))| (candidate, command, command_start))
+        };
+
+        let matches = substring_matches(pairs(), "eslint.", false);
+        assert_eq!(
+            matches
+                .iter()
+                .map(|mat| mat.candidate_id)
+                .collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(matches[1].positions, (16..23).collect::<Vec<_>>());
+
+        // The title isn't part of the command id.
+        assert!(substring_matches(pairs(), "Restart", true).is_empty());
+        assert_eq!(substring_matches(pairs(), "RESTART", false).len(), 1);
+        assert!(substring_matches(pairs(), "RESTART", true).is_empty());
+        assert_eq!(
+            substring_matches(pairs(), "fixes", false)[0].positions,
+            vec![23, 24, 25, 26, 27]
+        );
+    }
+
+    #[test]
+    fn test_command_description() {
+        assert_eq!(
+            command_description(None, "rust-analyzer.reloadWorkspace", "rust-analyzer"),
+            "rust-analyzer.reloadWorkspace on rust-analyzer"
+        );
+        assert_eq!(
+            command_description(Some("Run Test"), "rust-analyzer.runSingle", "rust-analyzer"),
+            "Run Test (rust-analyzer.runSingle) on rust-analyzer"
+        );
+    }
+
+    #[test]
+    fn test_segment_positions() {
+        // "tsserver: Organize Imports _typescript.organizeImports"
+        let positions = [0, 10, 11, 27, 39];
+        assert_eq!(segment_positions(&positions, 0, "tsserver"), vec![0]);
+        assert_eq!(
+            segment_positions(&positions, 10, "Organize Imports"),
+            vec![0, 1]
+        );
+        assert_eq!(
+            segment_positions(&positions, 27, "_typescript.organizeImports"),
+            vec![0, 12]
+        );
+        assert_eq!(
+            segment_positions(&[], 10, "Organize Imports"),
+            Vec::<usize>::new()
+        );
+
+        // "lua: Überprüfen lua.überprüfen", where "Ü" and "ü" take two bytes each.
+        let positions = [5, 7, 27];
+        assert_eq!(segment_positions(&positions, 5, "Überprüfen"), vec![0, 2]);
+        assert_eq!(segment_positions(&positions, 18, "lua.überprüfen"), vec![9]);
+        // A position in the middle of "ü" can't be highlighted.
+        assert_eq!(
+            segment_positions(&[6], 5, "Überprüfen"),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn test_command_result_message() {
+        let elapsed = Duration::from_millis(1234);
+        assert_eq!(
+            command_result_message("fix", vec![("eslint".into(), Ok(0))], elapsed),
+            "Ran fix in 1.2s"
+        );
+        assert_eq!(
+            command_result_message("fix", vec![("eslint".into(), Ok(3))], elapsed),
+            "Ran fix in 1.2s, which edited 3 files"
+        );
+        assert_eq!(
+            command_result_message(
+                "fix",
+                vec![("eslint".into(), Err(anyhow!("no config")))],
+                elapsed
+            ),
+            "fix failed: no config"
+        );
+        assert_eq!(
+            command_result_message(
+                "fix",
+                vec![("eslint".into(), Ok(1)), ("biome".into(), Ok(0))],
+                elapsed
+            ),
+            "Ran fix on 2 servers in 1.2s, which edited 1 file"
+        );
+        assert_eq!(
+            command_result_message(
+                "fix",
+                vec![
+                    ("eslint".into(), Ok(1)),
+                    ("biome".into(), Err(anyhow!("no config")))
+                ],
+                elapsed
+            ),
+            "fix failed on 1 of 2 servers. biome: no config"
+        );
+    }
+
+    #[test]
+    fn test_command_error_message() {
+        let response_error = |code| {
+            anyhow::Error::new(lsp::ResponseError {
+                code,
+                message: "missing uri".to_string(),
+            })
+        };
+        assert_eq!(
+            command_error_message(&response_error(Some(-32602))),
+            "the server returned error -32602 (invalid params): missing uri"
+        );
+        assert_eq!(
+            command_error_message(&response_error(Some(1))),
+            "the server returned error 1: missing uri"
+        );
+        assert_eq!(
+            command_error_message(&response_error(None).context("apply workspace edit")),
+            "the server returned an error: missing uri"
+        );
+        assert_eq!(
+            command_error_message(
+                &anyhow!("Server reset the connection").context("request to eslint failed")
+            ),
+            "request to eslint failed: Server reset the connection"
+        );
+    }
+
+    #[test]
+    fn test_locations_from_response() {
+        let location = json!({
+            "uri": "file:///dir/generated.rs",
+            "range": {
+                "start": { "line": 1, "character": 0 },
+                "end": { "line": 1, "character": 4 }
+            }
+        });
+        assert_eq!(
+            locations_from_response(location.clone()).map(|locations| locations.len()),
+            Some(1)
+        );
+        assert_eq!(
+            locations_from_response(json!([location.clone(), location]))
+                .map(|locations| locations.len()),
+            Some(2)
+        );
+        assert_eq!(locations_from_response(json!([])), None);
+        assert_eq!(
+            locations_from_response(json!({ "uri": "file:///a.rs" })),
+            None
+        );
+        assert_eq!(locations_from_response(json!("done")), None);
+    }
+
+    #[test]
+    fn test_queue_result_message() {
+        assert_eq!(
+            queue_result_message(1, 1, &[], false),
+            "Ran 1 queued command"
+        );
+        assert_eq!(
+            queue_result_message(3, 3, &["b".to_string()], false),
+            "Ran 3 queued commands, 1 failed: b"
+        );
+        assert_eq!(
+            queue_result_message(2, 3, &["a".to_string()], true),
+            "Cancelled the queued commands after 2 of 3, 1 failed: a"
+        );
+        assert_eq!(
+            queue_result_message(1, 3, &["a".to_string()], false),
+            "Stopped the queued commands after 1 of 3, 1 failed: a"
+        );
+    }
+
+    #[test]
+    fn test_command_result_text() {
+        assert_eq!(
+            command_result_text(
+                "deno.cache",
+                &[json!("file:///a.ts")],
+                Some(&json!({"cached": 1}))
+            )
+            .unwrap(),
+            "// Result of deno.cache\n// Arguments: [\"file:///a.ts\"]\n{\n  \"cached\": 1\n}\n"
+        );
+        assert_eq!(
+            command_result_text("deno.reload", &[], None).unwrap(),
+            "// Result of deno.reload\n// Arguments: []\nnull\n"
+        );
+    }
+}
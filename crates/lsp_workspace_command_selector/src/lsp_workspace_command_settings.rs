@@ -0,0 +1,361 @@
+use anyhow::Result;
+use collections::HashMap;
+use gpui::App;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use settings::{Settings, SettingsSources};
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LspWorkspaceCommandSettings {
+    pub document_uri_commands: Vec<String>,
+    pub timeout_ms: u64,
+    pub frecency_weight: f64,
+    pub denied_commands: Vec<String>,
+    pub allowed_commands: HashMap<String, Vec<String>>,
+    pub confirm_commands: Vec<String>,
+    pub preview_edits: bool,
+    pub max_matches: usize,
+    pub argument_commands: Vec<String>,
+    pub button: bool,
+    pub restore_query: bool,
+    pub command_aliases: HashMap<String, String>,
+    pub command_macros: HashMap<String, Vec<MacroStep>>,
+    pub stop_macro_on_failure: bool,
+    pub skip_refresh_commands: Vec<String>,
+    pub primary_server_only: bool,
+    pub argument_forms: HashMap<String, Vec<ArgumentField>>,
+    pub detailed_telemetry: bool,
+    pub modal_width: f32,
+    pub restart_stopped_servers: RestartStoppedServers,
+    pub history_size: usize,
+}
+
+/// A command run by a macro, once the commands before it have finished.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MacroStep {
+    /// The id of the command, such as `rust-analyzer.reloadWorkspace`.
+    pub command: String,
+    /// The arguments to send with the command, which may use the same templates as the
+    /// arguments entered in the selector.
+    #[serde(default)]
+    pub arguments: Vec<Value>,
+}
+
+const MIN_MODAL_WIDTH: f32 = 20.;
+const MAX_MODAL_WIDTH: f32 = 80.;
+
+/// A labeled input of a command's arguments form.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ArgumentField {
+    /// The key of the field in the object sent as the command's argument.
+    pub name: String,
+    /// The kind of value the field takes.
+    #[serde(rename = "type")]
+    pub kind: ArgumentFieldKind,
+    /// The values to choose from, for `enum` fields.
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentFieldKind {
+    String,
+    Number,
+    Bool,
+    Enum,
+}
+
+/// Whether to restart a stopped language server to run one of its commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartStoppedServers {
+    /// Ask whether to restart the server.
+    Ask,
+    /// Restart the server without asking.
+    Always,
+    /// Only report that the server isn't running.
+    Never,
+}
+
+impl LspWorkspaceCommandSettings {
+    /// Whether the selector lists the server's command: it must be allowed for servers with an
+    /// allowlist, and must not be denied.
+    pub fn is_listed(&self, server_name: &str, command: &str) -> bool {
+        let allowed = self
+            .allowed_commands
+            .get(server_name)
+            .is_none_or(|patterns| {
+                patterns
+                    .iter()
+                    .any(|pattern| matches_pattern(pattern, command))
+            });
+        allowed
+            && !self
+                .denied_commands
+                .iter()
+                .any(|pattern| matches_pattern(pattern, command))
+    }
+
+    pub fn requires_confirmation(&self, command: &str) -> bool {
+        self.confirm_commands
+            .iter()
+            .any(|pattern| matches_pattern(pattern, command))
+    }
+
+    /// The aliases of the command, in alphabetical order.
+    pub fn aliases_of(&self, command: &str) -> Vec<String> {
+        let mut aliases = self
+            .command_aliases
+            .iter()
+            .filter(|(_, aliased_command)| *aliased_command == command)
+            .map(|(alias, _)| alias.clone())
+            .collect::<Vec<_>>();
+        aliases.sort();
+        aliases
+    }
+
+    /// Whether to refresh the inlay hints and diagnostics of the affected buffers once the
+    /// command has run.
+    pub fn refreshes_after(&self, command: &str) -> bool {
+        !self
+            .skip_refresh_commands
+            .iter()
+            .any(|pattern| matches_pattern(pattern, command))
+    }
+
+    /// The width of the selector and the arguments prompt in rems, kept within the range that
+    /// fits on screen and leaves room for a command.
+    pub fn modal_width(&self) -> f32 {
+        self.modal_width.clamp(MIN_MODAL_WIDTH, MAX_MODAL_WIDTH)
+    }
+
+    pub fn takes_arguments(&self, command: &str) -> bool {
+        self.argument_commands
+            .iter()
+            .any(|pattern| matches_pattern(pattern, command))
+    }
+}
+
+/// Matches a command id against a pattern in which `*` stands for any run of characters.
+fn matches_pattern(pattern: &str, command: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(prefix) = parts.next() else {
+        return false;
+    };
+    let Some(mut rest) = command.strip_prefix(prefix) else {
+        return false;
+    };
+    let Some(suffix) = parts.next_back() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(ix) => rest = &rest[ix + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(suffix)
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct LspWorkspaceCommandSettingsContent {
+    /// Commands that receive the buffer's text document identifier, `{ "uri": ... }`,
+    /// as their first argument. Nothing is prepended for buffers that are not backed by a file.
+    ///
+    /// Default: []
+    pub document_uri_commands: Option<Vec<String>>,
+    /// How long to wait for a language server to finish running a command before cancelling it.
+    /// When set to 0, waits indefinitely.
+    ///
+    /// Default: 30000
+    pub timeout_ms: Option<u64>,
+    /// How much running a command often and recently ranks it above other matches for a query.
+    /// When set to 0, commands are ranked by how well they match the query alone.
+    ///
+    /// Default: 0.2
+    pub frecency_weight: Option<f64>,
+    /// Commands to leave out of the selector, such as internal or debugging commands. A `*`
+    /// matches any run of characters, so `rust-analyzer.internal.*` hides every command
+    /// under that prefix.
+    ///
+    /// Default: []
+    pub denied_commands: Option<Vec<String>>,
+    /// The only commands to list for the given language servers, keyed by server name, with the
+    /// same patterns as `denied_commands`. Servers without an entry list all of their commands.
+    ///
+    /// Default: {}
+    pub allowed_commands: Option<HashMap<String, Vec<String>>>,
+    /// Commands to ask for confirmation before running, as they may be destructive, with the
+    /// same patterns as `denied_commands`.
+    ///
+    /// Default: ["*.reset*", "*.clean*", "*.delete*", "*.remove*"]
+    pub confirm_commands: Option<Vec<String>>,
+    /// Whether to preview the edits that a command returns as diffs against the files, and
+    /// only apply them once they're confirmed. Edits that a server applies itself while running
+    /// the command are applied right away.
+    ///
+    /// Default: false
+    pub preview_edits: Option<bool>,
+    /// How many of the best matches for a query to list. The selector notes when there are
+    /// more commands than are listed.
+    ///
+    /// Default: 100
+    pub max_matches: Option<usize>,
+    /// Commands known to take arguments, with the same patterns as `denied_commands`, which the
+    /// selector marks as such. Servers don't advertise which of their commands take arguments.
+    ///
+    /// Default: []
+    pub argument_commands: Option<Vec<String>>,
+    /// Whether to show a button in the status bar with the number of commands available for
+    /// the active buffer, which opens the selector. It's hidden while there are none.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Whether to open the selector with the query it was last closed with, as long as the
+    /// active buffer's language hasn't changed since.
+    ///
+    /// Default: false
+    pub restore_query: Option<bool>,
+    /// Short names to find commands by, mapped to the ids of the commands they stand for, such
+    /// as `{ "rw": "rust-analyzer.reloadWorkspace" }`. A query that's an alias ranks its
+    /// command first.
+    ///
+    /// Default: {}
+    pub command_aliases: Option<HashMap<String, String>>,
+    /// Named sequences of commands, which `lsp_workspace_command_selector::RunMacro` runs one
+    /// after another on the active buffer's language servers, each once the one before it has
+    /// finished.
+    ///
+    /// Default: {}
+    pub command_macros: Option<HashMap<String, Vec<MacroStep>>>,
+    /// Whether a macro stops at the first command that fails, rather than running the rest of
+    /// its commands regardless.
+    ///
+    /// Default: true
+    pub stop_macro_on_failure: Option<bool>,
+    /// Commands after which the inlay hints and diagnostics of the buffers they ran against and
+    /// edited aren't refreshed, with the same patterns as `denied_commands`, for commands that
+    /// don't change what the language servers know about the project.
+    ///
+    /// Default: []
+    pub skip_refresh_commands: Option<Vec<String>>,
+    /// Whether to only list the commands of each buffer's primary language server, the first one
+    /// configured for its language, leaving out those of auxiliary servers such as linters.
+    ///
+    /// Default: false
+    pub primary_server_only: Option<bool>,
+    /// Forms to enter the arguments of the given commands with, keyed by command id, rather than
+    /// as raw JSON. The command receives a single object with a key for each field that isn't
+    /// left empty, and string fields may use the same templates as raw arguments.
+    ///
+    /// Default: {}
+    pub argument_forms: Option<HashMap<String, Vec<ArgumentField>>>,
+    /// Whether the telemetry sent about running a command includes the command's id, rather
+    /// than just the name of the server and whether and how quickly it succeeded. Telemetry is
+    /// only sent with `telemetry.metrics` enabled, and never includes the arguments.
+    ///
+    /// Default: false
+    pub detailed_telemetry: Option<bool>,
+    /// The width of the selector and the arguments prompt in rems, between 20 and 80, for
+    /// reading long command ids and server names.
+    ///
+    /// Default: 34
+    pub modal_width: Option<f32>,
+    /// Whether to restart a language server that has stopped when running one of its commands,
+    /// and run the command once it has started again: "ask", "always" or "never".
+    ///
+    /// Default: "ask"
+    pub restart_stopped_servers: Option<RestartStoppedServers>,
+    /// How many of the most recently run commands of each workspace to remember, which are
+    /// listed first without a query.
+    ///
+    /// Default: 10
+    pub history_size: Option<usize>,
+}
+
+impl Settings for LspWorkspaceCommandSettings {
+    const KEY: Option<&'static str> = Some("lsp_workspace_commands");
+
+    type FileContent = LspWorkspaceCommandSettingsContent;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut App) -> Result<Self> {
+        sources.json_merge()
+    }
+
+    fn import_from_vscode(_vscode: &settings::VsCodeSettings, _current: &mut Self::FileContent) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern(
+            "rust-analyzer.reload",
+            "rust-analyzer.reload"
+        ));
+        assert!(!matches_pattern(
+            "rust-analyzer.reload",
+            "rust-analyzer.reloadWorkspace"
+        ));
+        assert!(matches_pattern(
+            "rust-analyzer.internal.*",
+            "rust-analyzer.internal.debug"
+        ));
+        assert!(!matches_pattern(
+            "rust-analyzer.internal.*",
+            "rust-analyzer.run"
+        ));
+        assert!(matches_pattern("*.debug*", "eslint.debugInfo"));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(!matches_pattern("a*b*c", "acb"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("*", "anything"));
+    }
+
+    #[test]
+    fn test_is_listed() {
+        let settings = LspWorkspaceCommandSettings {
+            document_uri_commands: Vec::new(),
+            timeout_ms: 0,
+            frecency_weight: 0.,
+            denied_commands: vec!["*.internal.*".to_string()],
+            allowed_commands: HashMap::from_iter([(
+                "eslint".to_string(),
+                vec![
+                    "eslint.applyAllFixes".to_string(),
+                    "eslint.internal.*".to_string(),
+                ],
+            )]),
+            confirm_commands: Vec::new(),
+            preview_edits: false,
+            max_matches: 100,
+            argument_commands: Vec::new(),
+            button: true,
+            restore_query: false,
+            command_aliases: HashMap::default(),
+            command_macros: HashMap::default(),
+            stop_macro_on_failure: true,
+            skip_refresh_commands: vec!["rust-analyzer.view*".to_string()],
+            primary_server_only: false,
+            argument_forms: HashMap::default(),
+            detailed_telemetry: false,
+            modal_width: 120.,
+            restart_stopped_servers: RestartStoppedServers::Ask,
+            history_size: 10,
+        };
+        assert!(settings.is_listed("eslint", "eslint.applyAllFixes"));
+        assert!(!settings.is_listed("eslint", "eslint.restart"));
+        assert!(!settings.is_listed("eslint", "eslint.internal.debug"));
+        assert!(settings.is_listed("rust-analyzer", "rust-analyzer.reloadWorkspace"));
+        assert!(!settings.is_listed("rust-analyzer", "rust-analyzer.internal.debug"));
+        assert!(settings.refreshes_after("rust-analyzer.reloadWorkspace"));
+        assert!(!settings.refreshes_after("rust-analyzer.viewHir"));
+        assert_eq!(settings.modal_width(), MAX_MODAL_WIDTH);
+    }
+}
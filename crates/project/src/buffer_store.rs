@@ -91,6 +91,26 @@ pub enum BufferStoreEvent {
 #[derive(Default, Debug)]
 pub struct ProjectTransaction(pub HashMap<Entity<Buffer>, language::Transaction>);
 
+impl ProjectTransaction {
+    /// Adds the edits of `other` to this transaction. When both edited the same buffer, the
+    /// buffer's transactions are merged into one, so that they're undone together.
+    pub fn merge(&mut self, other: ProjectTransaction, cx: &mut App) {
+        for (buffer, transaction) in other.0 {
+            match self.0.get_mut(&buffer) {
+                Some(destination) => {
+                    buffer.update(cx, |buffer, _| {
+                        buffer.merge_transactions(transaction.id, destination.id);
+                    });
+                    destination.edit_ids.extend(transaction.edit_ids);
+                }
+                None => {
+                    self.0.insert(buffer, transaction);
+                }
+            }
+        }
+    }
+}
+
 impl EventEmitter<BufferStoreEvent> for BufferStore {}
 
 impl RemoteBufferStore {
@@ -266,6 +266,7 @@ mod tests {
             tags: vec![],
             show_summary: true,
             show_command: true,
+            lsp_command: false,
         };
 
         let scenario = locator
@@ -293,6 +294,7 @@ mod tests {
             tags: vec![],
             show_summary: true,
             show_command: true,
+            lsp_command: false,
         };
 
         let scenario = locator
@@ -431,6 +433,7 @@ mod tests {
             tags: vec![],
             show_summary: true,
             show_command: true,
+            lsp_command: false,
         };
 
         let scenario = locator
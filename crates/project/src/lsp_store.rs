@@ -30,7 +30,7 @@ use clock::Global;
 use collections::{BTreeMap, BTreeSet, HashMap, HashSet, btree_map};
 use futures::{
     AsyncWriteExt, Future, FutureExt, StreamExt,
-    future::{Either, Shared, join_all, pending, select},
+    future::{Either, Shared, join_all, pending, select, try_join_all},
     select, select_biased,
     stream::FuturesUnordered,
 };
@@ -152,11 +152,18 @@ pub struct LocalLspStore {
     pub language_servers: HashMap<LanguageServerId, LanguageServerState>,
     buffers_being_formatted: HashSet<BufferId>,
     last_workspace_edits_by_language_server: HashMap<LanguageServerId, ProjectTransaction>,
+    /// The edits that servers applied through `workspace/applyEdit` while each of the workspace
+    /// commands in flight ran, by the order the commands were started in.
+    workspace_command_edits: BTreeMap<usize, (LanguageServerId, ProjectTransaction)>,
+    next_workspace_command_id: usize,
     language_server_watched_paths: HashMap<LanguageServerId, LanguageServerWatchedPaths>,
     language_server_paths_watched_for_rename:
         HashMap<LanguageServerId, RenamePathsWatchedForServer>,
     language_server_watcher_registrations:
         HashMap<LanguageServerId, HashMap<String, Vec<FileSystemWatcher>>>,
+    /// The workspace commands that each server registered dynamically, by registration id,
+    /// leaving out those it already advertised, so that unregistering removes them again.
+    execute_command_registrations: HashMap<LanguageServerId, HashMap<String, Vec<String>>>,
     supplementary_language_servers:
         HashMap<LanguageServerId, (LanguageServerName, Arc<LanguageServer>)>,
     prettier_store: Entity<PrettierStore>,
@@ -741,6 +748,44 @@ impl LocalLspStore {
                                         anyhow::Ok(())
                                     })??;
                                 }
+                                "workspace/executeCommand" => {
+                                    this.update(&mut cx, |this, cx| {
+                                        if let Some(server) = this.language_server_for_id(server_id)
+                                            && let Some(options) = reg.register_options
+                                        {
+                                            let options = serde_json::from_value::<
+                                                lsp::ExecuteCommandOptions,
+                                            >(
+                                                options
+                                            )?;
+                                            let mut registered = Vec::new();
+                                            server.update_capabilities(|capabilities| {
+                                                let provider = capabilities
+                                                    .execute_command_provider
+                                                    .get_or_insert_default();
+                                                for command in options.commands {
+                                                    if !provider.commands.contains(&command) {
+                                                        provider.commands.push(command.clone());
+                                                        registered.push(command);
+                                                    }
+                                                }
+                                            });
+                                            if let Some(local) = this.as_local_mut() {
+                                                local
+                                                    .execute_command_registrations
+                                                    .entry(server_id)
+                                                    .or_default()
+                                                    .insert(reg.id.clone(), registered);
+                                            }
+                                            cx.emit(
+                                                LspStoreEvent::LanguageServerCapabilitiesUpdated(
+                                                    server_id,
+                                                ),
+                                            );
+                                        }
+                                        anyhow::Ok(())
+                                    })??;
+                                }
                                 _ => log::warn!("unhandled capability registration: {reg:?}"),
                             }
                         }
@@ -813,6 +858,41 @@ impl LocalLspStore {
                                         }
                                     })?;
                                 }
+                                "workspace/executeCommand" => {
+                                    this.update(&mut cx, |this, cx| {
+                                        let Some(unregistered) =
+                                            this.as_local_mut().and_then(|local| {
+                                                local
+                                                    .execute_command_registrations
+                                                    .get_mut(&server_id)?
+                                                    .remove(&unreg.id)
+                                            })
+                                        else {
+                                            log::warn!(
+                                                "language server {server_id}: failed to unregister workspace/executeCommand capability with id {}. not registered.",
+                                                unreg.id
+                                            );
+                                            return;
+                                        };
+                                        if let Some(server) = this.language_server_for_id(server_id)
+                                        {
+                                            server.update_capabilities(|capabilities| {
+                                                if let Some(provider) =
+                                                    &mut capabilities.execute_command_provider
+                                                {
+                                                    provider.commands.retain(|command| {
+                                                        !unregistered.contains(command)
+                                                    });
+                                                }
+                                            });
+                                            cx.emit(
+                                                LspStoreEvent::LanguageServerCapabilitiesUpdated(
+                                                    server_id,
+                                                ),
+                                            );
+                                        }
+                                    })?;
+                                }
                                 _ => log::warn!("unhandled capability unregistration: {unreg:?}"),
                             }
                         }
@@ -3144,12 +3224,24 @@ impl LocalLspStore {
         )
         .await
         .log_err();
-        this.update(cx, |this, _| {
-            if let Some(transaction) = transaction {
-                this.as_local_mut()
-                    .unwrap()
-                    .last_workspace_edits_by_language_server
-                    .insert(server_id, transaction);
+        this.update(cx, |this, cx| {
+            if let Some(transaction) = transaction
+                && let Some(local) = this.as_local_mut()
+            {
+                // Requests don't say which command they're made for, so the edits are given to
+                // the server's oldest command in flight, which servers usually handle first.
+                let command_edits = local
+                    .workspace_command_edits
+                    .values_mut()
+                    .find(|(command_server_id, _)| *command_server_id == server_id);
+                match command_edits {
+                    Some((_, command_edits)) => command_edits.merge(transaction, cx),
+                    None => {
+                        local
+                            .last_workspace_edits_by_language_server
+                            .insert(server_id, transaction);
+                    }
+                }
             }
         })?;
         Ok(lsp::ApplyWorkspaceEditResponse {
@@ -3566,10 +3658,24 @@ pub enum LspFetchStrategy {
     UseCache { known_cache_version: Option<usize> },
 }
 
+/// The workspace commands that one of a buffer's language servers can execute.
+#[derive(Clone, Debug)]
+pub struct LanguageServerCommands {
+    pub server_id: LanguageServerId,
+    pub server_name: LanguageServerName,
+    pub commands: Vec<String>,
+    /// Whether this is the first of the buffer's language servers, the one configured for its
+    /// language ahead of auxiliary servers such as linters.
+    pub primary: bool,
+    /// The root of the worktree the server was started for.
+    pub root_path: Option<Arc<Path>>,
+}
+
 #[derive(Debug)]
 pub enum LspStoreEvent {
     LanguageServerAdded(LanguageServerId, LanguageServerName, Option<WorktreeId>),
     LanguageServerRemoved(LanguageServerId),
+    LanguageServerCapabilitiesUpdated(LanguageServerId),
     LanguageServerUpdate {
         language_server_id: LanguageServerId,
         name: Option<LanguageServerName>,
@@ -3647,6 +3753,8 @@ impl LspStore {
         client.add_entity_request_handler(Self::handle_register_buffer_with_language_servers);
         client.add_entity_request_handler(Self::handle_rename_project_entry);
         client.add_entity_request_handler(Self::handle_language_server_id_for_name);
+        client.add_entity_request_handler(Self::handle_get_language_server_commands);
+        client.add_entity_request_handler(Self::handle_execute_language_server_command);
         client.add_entity_request_handler(Self::handle_pull_workspace_diagnostics);
         client.add_entity_request_handler(Self::handle_lsp_command::<GetCodeActions>);
         client.add_entity_request_handler(Self::handle_lsp_command::<GetCompletions>);
@@ -3763,9 +3871,12 @@ impl LspStore {
                 language_server_ids: Default::default(),
                 language_servers: Default::default(),
                 last_workspace_edits_by_language_server: Default::default(),
+                workspace_command_edits: Default::default(),
+                next_workspace_command_id: 0,
                 language_server_watched_paths: Default::default(),
                 language_server_paths_watched_for_rename: Default::default(),
                 language_server_watcher_registrations: Default::default(),
+                execute_command_registrations: Default::default(),
                 buffers_being_formatted: Default::default(),
                 buffer_snapshots: Default::default(),
                 prettier_store,
@@ -4838,6 +4949,250 @@ impl LspStore {
         }
     }
 
+    /// Applies a workspace edit that the given language server produced outside of a code action,
+    /// for example as the result of a workspace command.
+    pub fn apply_workspace_edit(
+        &self,
+        server_id: LanguageServerId,
+        edit: lsp::WorkspaceEdit,
+        push_to_history: bool,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        let Some((lsp_adapter, language_server)) = self
+            .language_server_adapter_for_id(server_id)
+            .zip(self.language_server_for_id(server_id))
+        else {
+            return Task::ready(Err(anyhow!("language server {server_id} is not running")));
+        };
+        cx.spawn(async move |this, cx| {
+            LocalLspStore::deserialize_workspace_edit(
+                this.upgrade().context("no app present")?,
+                edit,
+                push_to_history,
+                lsp_adapter,
+                language_server,
+                cx,
+            )
+            .await
+        })
+    }
+
+    /// Lists the workspace commands that the language servers of the buffer can execute, asking
+    /// the host for them in remote projects.
+    pub fn workspace_commands(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<LanguageServerCommands>>> {
+        if let Some((upstream_client, project_id)) = self.upstream_client() {
+            let request = proto::GetLanguageServerCommands {
+                project_id,
+                buffer_id: buffer.read(cx).remote_id().into(),
+            };
+            cx.background_spawn(async move {
+                let response = upstream_client.request(request).await?;
+                Ok(response
+                    .servers
+                    .into_iter()
+                    .map(|server| LanguageServerCommands {
+                        server_id: LanguageServerId::from_proto(server.server_id),
+                        server_name: LanguageServerName::from_proto(server.server_name),
+                        commands: server.commands,
+                        primary: server.primary,
+                        root_path: server.root_path.map(|path| Arc::from(PathBuf::from(path))),
+                    })
+                    .collect())
+            })
+        } else {
+            Task::ready(Ok(self.local_workspace_commands(buffer, cx)))
+        }
+    }
+
+    fn local_workspace_commands(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut App,
+    ) -> Vec<LanguageServerCommands> {
+        let mut servers = buffer.update(cx, |buffer, cx| {
+            self.language_servers_for_local_buffer(buffer, cx)
+                .enumerate()
+                .filter_map(|(ix, (_, server))| {
+                    let provider = server.capabilities().execute_command_provider?;
+                    Some(LanguageServerCommands {
+                        server_id: server.server_id(),
+                        server_name: server.name(),
+                        commands: provider.commands,
+                        primary: ix == 0,
+                        root_path: None,
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        for server in &mut servers {
+            server.root_path = self.language_server_root(server.server_id, cx);
+        }
+        servers
+    }
+
+    /// The root of the worktree that a local language server was started for, which tells apart
+    /// the instances of a server in a project with several worktrees.
+    pub fn language_server_root(&self, server_id: LanguageServerId, cx: &App) -> Option<Arc<Path>> {
+        let local = self.as_local()?;
+        let (worktree_id, _) = local
+            .language_server_ids
+            .iter()
+            .find_map(|(key, server_ids)| server_ids.contains(&server_id).then_some(key))?;
+        let worktree = self
+            .worktree_store
+            .read(cx)
+            .worktree_for_id(*worktree_id, cx)?;
+        Some(worktree.read(cx).abs_path())
+    }
+
+    /// Executes a workspace command on the given language server, on the host in remote projects,
+    /// and collects the edits it made, whether the server applied them through
+    /// `workspace/applyEdit` or returned them as the command's result. The result is returned
+    /// along with the edits, for commands that return something else, such as a location.
+    /// Without `apply_returned_edits`, an edit that the command returns is left in its result,
+    /// such as to be previewed, and only the edits the server applied itself are collected.
+    pub fn execute_workspace_command(
+        &mut self,
+        server_id: LanguageServerId,
+        command: String,
+        arguments: Vec<serde_json::Value>,
+        timeout: Option<Duration>,
+        apply_returned_edits: bool,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<(ProjectTransaction, Option<serde_json::Value>)>> {
+        if let Some((upstream_client, project_id)) = self.upstream_client() {
+            let request = proto::ExecuteLanguageServerCommand {
+                project_id,
+                language_server_id: server_id.to_proto(),
+                command,
+                arguments: arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect(),
+                timeout_ms: timeout.map(|timeout| timeout.as_millis() as u64),
+                return_result: false,
+                skip_returned_edits: !apply_returned_edits,
+            };
+            let buffer_store = self.buffer_store();
+            return cx.spawn(async move |_, cx| {
+                let response = upstream_client.request(request).await?;
+                let result = response
+                    .result
+                    .map(|result| serde_json::from_str(&result))
+                    .transpose()
+                    .context("invalid workspace command result")?;
+                let transaction = response.transaction.context("missing transaction")?;
+                let transaction = buffer_store
+                    .update(cx, |buffer_store, cx| {
+                        buffer_store.deserialize_project_transaction(transaction, true, cx)
+                    })?
+                    .await?;
+                Ok((transaction, result))
+            });
+        }
+
+        let Some(server) = self.language_server_for_id(server_id) else {
+            return Task::ready(Err(anyhow!(
+                "language server {server_id} is no longer running"
+            )));
+        };
+        let Some(local) = self.as_local_mut() else {
+            return Task::ready(Err(anyhow!("no upstream client and not local")));
+        };
+        let command_id = post_inc(&mut local.next_workspace_command_id);
+        local
+            .workspace_command_edits
+            .insert(command_id, (server_id, ProjectTransaction::default()));
+        let request = request_workspace_command(server, command, arguments, timeout, cx);
+        cx.spawn(async move |lsp_store, cx| {
+            let response = request.await;
+            let mut transaction = lsp_store.update(cx, |lsp_store, _| {
+                lsp_store
+                    .as_local_mut()
+                    .and_then(|local| local.workspace_command_edits.remove(&command_id))
+                    .map(|(_, transaction)| transaction)
+                    .unwrap_or_default()
+            })?;
+            let response = response?;
+            if apply_returned_edits
+                && let Some(edit) = response.clone().and_then(workspace_edit_from_response)
+            {
+                let response_transaction = lsp_store
+                    .update(cx, |lsp_store, cx| {
+                        lsp_store.apply_workspace_edit(server_id, edit, true, cx)
+                    })?
+                    .await
+                    .context("apply workspace edit returned by workspace command")?;
+                cx.update(|cx| transaction.merge(response_transaction, cx))?;
+            }
+            Ok((transaction, response))
+        })
+    }
+
+    /// Executes a workspace command like [`Self::execute_workspace_command`], but returns its
+    /// result as is rather than applying the edits it may contain.
+    pub fn workspace_command_result(
+        &mut self,
+        server_id: LanguageServerId,
+        command: String,
+        arguments: Vec<serde_json::Value>,
+        timeout: Option<Duration>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Option<serde_json::Value>>> {
+        if let Some((upstream_client, project_id)) = self.upstream_client() {
+            let request = proto::ExecuteLanguageServerCommand {
+                project_id,
+                language_server_id: server_id.to_proto(),
+                command,
+                arguments: arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect(),
+                timeout_ms: timeout.map(|timeout| timeout.as_millis() as u64),
+                return_result: true,
+                skip_returned_edits: false,
+            };
+            return cx.background_spawn(async move {
+                let response = upstream_client.request(request).await?;
+                response
+                    .result
+                    .map(|result| serde_json::from_str(&result))
+                    .transpose()
+                    .context("invalid workspace command result")
+            });
+        }
+
+        let Some(server) = self.language_server_for_id(server_id) else {
+            return Task::ready(Err(anyhow!("language server {server_id} not found")));
+        };
+        cx.background_spawn(request_workspace_command(
+            server, command, arguments, timeout, cx,
+        ))
+    }
+
+    /// Queries the inlay hints of the editors anew and pulls the diagnostics of the buffers
+    /// again, after something such as a workspace command may have changed what the language
+    /// servers know about the project.
+    pub fn refresh_hints_and_diagnostics(
+        &mut self,
+        buffers: Vec<Entity<Buffer>>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        cx.emit(LspStoreEvent::RefreshInlayHints);
+        let pulls = buffers
+            .into_iter()
+            .map(|buffer| self.pull_diagnostics_for_buffer(buffer, cx))
+            .collect::<Vec<_>>();
+        cx.background_spawn(async move {
+            try_join_all(pulls).await?;
+            Ok(())
+        })
+    }
+
     pub fn apply_code_action_kind(
         &mut self,
         buffers: HashSet<Entity<Buffer>>,
@@ -5670,6 +6025,27 @@ impl LspStore {
         }
     }
 
+    /// Returns the titles of the commands in the code lenses already fetched for the buffer,
+    /// keyed by server and command id, without querying the language servers.
+    pub fn cached_code_lens_command_titles(
+        &self,
+        buffer_id: BufferId,
+    ) -> HashMap<(LanguageServerId, String), String> {
+        let Some(code_lens) = self.lsp_code_lens.get(&buffer_id) else {
+            return HashMap::default();
+        };
+        code_lens
+            .lens
+            .iter()
+            .flat_map(|(server_id, actions)| {
+                actions.iter().filter_map(move |action| {
+                    let command = action.lsp_action.command()?;
+                    Some(((*server_id, command.command.clone()), command.title.clone()))
+                })
+            })
+            .collect()
+    }
+
     pub fn code_lens_actions(
         &mut self,
         buffer: &Entity<Buffer>,
@@ -7509,6 +7885,16 @@ impl LspStore {
         })
     }
 
+    /// Whether any of the buffer's language servers can execute workspace commands. In remote
+    /// projects, where the host knows the capabilities of the servers, this is always true.
+    pub fn offers_workspace_commands(&self, buffer: &Entity<Buffer>, cx: &mut App) -> bool {
+        self.as_local().is_none()
+            || buffer.update(cx, |buffer, cx| {
+                self.language_servers_for_local_buffer(buffer, cx)
+                    .any(|(_, server)| server.capabilities().execute_command_provider.is_some())
+            })
+    }
+
     pub fn language_servers_for_local_buffer<'a>(
         &'a self,
         buffer: &Buffer,
@@ -8640,6 +9026,103 @@ impl LspStore {
             })
     }
 
+    async fn handle_get_language_server_commands(
+        lsp_store: Entity<Self>,
+        envelope: TypedEnvelope<proto::GetLanguageServerCommands>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GetLanguageServerCommandsResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let servers = lsp_store.update(&mut cx, |lsp_store, cx| {
+            let buffer = lsp_store.buffer_store.read(cx).get_existing(buffer_id)?;
+            anyhow::Ok(lsp_store.local_workspace_commands(&buffer, cx))
+        })??;
+        Ok(proto::GetLanguageServerCommandsResponse {
+            servers: servers
+                .into_iter()
+                .map(|server| proto::LanguageServerCommands {
+                    server_id: server.server_id.to_proto(),
+                    server_name: server.server_name.0.to_string(),
+                    commands: server.commands,
+                    primary: server.primary,
+                    root_path: server
+                        .root_path
+                        .map(|path| path.to_string_lossy().into_owned()),
+                })
+                .collect(),
+        })
+    }
+
+    async fn handle_execute_language_server_command(
+        lsp_store: Entity<Self>,
+        envelope: TypedEnvelope<proto::ExecuteLanguageServerCommand>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::ExecuteLanguageServerCommandResponse> {
+        // Only requests forwarded from collaborators carry their original sender, unlike those
+        // of the host's own SSH client.
+        if envelope.original_sender_id.is_some() {
+            let command = &envelope.payload.command;
+            let allowed = lsp_store.read_with(&mut cx, |_, cx| {
+                ProjectSettings::get_global(cx)
+                    .global_lsp_settings
+                    .guest_workspace_commands
+                    .contains(command)
+            })?;
+            anyhow::ensure!(allowed, "the host doesn't allow guests to run {command}");
+        }
+        let sender_id = envelope.original_sender_id().unwrap_or_default();
+        let server_id = LanguageServerId::from_proto(envelope.payload.language_server_id);
+        let arguments = envelope
+            .payload
+            .arguments
+            .iter()
+            .map(|argument| serde_json::from_str(argument))
+            .collect::<Result<Vec<_>, _>>()
+            .context("invalid workspace command arguments")?;
+        let timeout = envelope.payload.timeout_ms.map(Duration::from_millis);
+        if envelope.payload.return_result {
+            let result = lsp_store
+                .update(&mut cx, |lsp_store, cx| {
+                    lsp_store.workspace_command_result(
+                        server_id,
+                        envelope.payload.command,
+                        arguments,
+                        timeout,
+                        cx,
+                    )
+                })?
+                .await?;
+            return Ok(proto::ExecuteLanguageServerCommandResponse {
+                transaction: None,
+                result: result.map(|result| result.to_string()),
+            });
+        }
+        let (project_transaction, result) = lsp_store
+            .update(&mut cx, |lsp_store, cx| {
+                lsp_store.execute_workspace_command(
+                    server_id,
+                    envelope.payload.command,
+                    arguments,
+                    timeout,
+                    !envelope.payload.skip_returned_edits,
+                    cx,
+                )
+            })?
+            .await?;
+        let project_transaction = lsp_store.update(&mut cx, |lsp_store, cx| {
+            lsp_store.buffer_store.update(cx, |buffer_store, cx| {
+                buffer_store.serialize_project_transaction_for_peer(
+                    project_transaction,
+                    sender_id,
+                    cx,
+                )
+            })
+        })?;
+        Ok(proto::ExecuteLanguageServerCommandResponse {
+            transaction: Some(project_transaction),
+            result: result.map(|result| result.to_string()),
+        })
+    }
+
     async fn handle_rename_project_entry(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::RenameProjectEntry>,
@@ -10267,6 +10750,7 @@ impl LspStore {
             });
         }
         local.language_server_watched_paths.remove(&server_id);
+        local.execute_command_registrations.remove(&server_id);
 
         let server_state = local.language_servers.remove(&server_id);
         self.cleanup_lsp_data(server_id);
@@ -11725,6 +12209,50 @@ fn resolve_word_completion(snapshot: &BufferSnapshot, completion: &mut Completio
 
 impl EventEmitter<LspStoreEvent> for LspStore {}
 
+/// Sends the command to the server, cancelling it once `timeout` elapses.
+fn request_workspace_command(
+    server: Arc<LanguageServer>,
+    command: String,
+    arguments: Vec<serde_json::Value>,
+    timeout: Option<Duration>,
+    cx: &App,
+) -> impl Future<Output = Result<Option<serde_json::Value>>> + use<> {
+    let executor = cx.background_executor().clone();
+    async move {
+        let timer = async move {
+            let Some(timeout) = timeout else {
+                return pending::<String>().await;
+            };
+            executor.timer(timeout).await;
+            format!("which took over {timeout:?}")
+        };
+        match server
+            .request_with_timer::<lsp::request::ExecuteCommand, _>(
+                lsp::ExecuteCommandParams {
+                    command,
+                    arguments,
+                    ..Default::default()
+                },
+                timer,
+            )
+            .await
+        {
+            ConnectionResult::Timeout => {
+                anyhow::bail!("command timed out after {:?}", timeout.unwrap_or_default())
+            }
+            response => response.into_response(),
+        }
+    }
+}
+
+/// Commands may return anything, so only treat the response as an edit when it actually
+/// carries changes: every field of [`lsp::WorkspaceEdit`] is optional, so any object would
+/// deserialize into one.
+pub fn workspace_edit_from_response(response: serde_json::Value) -> Option<lsp::WorkspaceEdit> {
+    let edit = serde_json::from_value::<lsp::WorkspaceEdit>(response).ok()?;
+    (edit.changes.is_some() || edit.document_changes.is_some()).then_some(edit)
+}
+
 fn remove_empty_hover_blocks(mut hover: Hover) -> Option<Hover> {
     hover
         .contents
@@ -12785,4 +13313,25 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_workspace_edit_from_response() {
+        assert!(workspace_edit_from_response(serde_json::json!(true)).is_none());
+        assert!(workspace_edit_from_response(serde_json::json!("done")).is_none());
+        assert!(workspace_edit_from_response(serde_json::json!({ "status": "ok" })).is_none());
+
+        let edit = workspace_edit_from_response(serde_json::json!({
+            "changes": {
+                "file:///a.rs": [{
+                    "range": {
+                        "start": { "line": 0, "character": 0 },
+                        "end": { "line": 0, "character": 3 }
+                    },
+                    "newText": "pub"
+                }]
+            }
+        }))
+        .unwrap();
+        assert_eq!(edit.changes.unwrap().len(), 1);
+    }
 }
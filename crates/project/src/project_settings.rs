@@ -120,6 +120,12 @@ pub struct GlobalLspSettings {
     /// Default: `true`
     #[serde(default = "default_true")]
     pub button: bool,
+    /// The LSP workspace commands that guests of a shared project may run
+    /// on its language servers.
+    ///
+    /// Default: `[]`
+    #[serde(default)]
+    pub guest_workspace_commands: Vec<String>,
 }
 
 impl ContextServerSettings {
@@ -287,6 +293,7 @@ impl Default for GlobalLspSettings {
     fn default() -> Self {
         Self {
             button: default_true(),
+            guest_workspace_commands: Vec::new(),
         }
     }
 }
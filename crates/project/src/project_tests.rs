@@ -1291,6 +1291,239 @@ async fn test_reporting_fs_changes_to_language_servers(cx: &mut gpui::TestAppCon
     );
 }
 
+#[gpui::test]
+async fn test_dynamically_registered_execute_commands(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "" })).await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let (language_registry, lsp_store) = project.read_with(cx, |project, _| {
+        (project.languages().clone(), project.lsp_store())
+    });
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                execute_command_provider: Some(lsp::ExecuteCommandOptions {
+                    commands: vec!["initial".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/a.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server
+        .request::<lsp::request::RegisterCapability>(lsp::RegistrationParams {
+            registrations: vec![
+                lsp::Registration {
+                    id: "first".to_string(),
+                    method: "workspace/executeCommand".to_string(),
+                    register_options: serde_json::to_value(lsp::ExecuteCommandOptions {
+                        commands: vec!["initial".to_string(), "registered".to_string()],
+                        ..Default::default()
+                    })
+                    .ok(),
+                },
+                lsp::Registration {
+                    id: "second".to_string(),
+                    method: "workspace/executeCommand".to_string(),
+                    register_options: serde_json::to_value(lsp::ExecuteCommandOptions {
+                        commands: vec!["other".to_string()],
+                        ..Default::default()
+                    })
+                    .ok(),
+                },
+            ],
+        })
+        .await
+        .into_response()
+        .unwrap();
+
+    lsp_store.read_with(cx, |lsp_store, _| {
+        let server = lsp_store
+            .language_server_for_id(fake_server.server.server_id())
+            .unwrap();
+        assert_eq!(
+            server
+                .capabilities()
+                .execute_command_provider
+                .map(|provider| provider.commands),
+            Some(vec![
+                "initial".to_string(),
+                "registered".to_string(),
+                "other".to_string()
+            ])
+        );
+    });
+
+    // Unregistering removes only the commands that the registration added, keeping those the
+    // server declared when it started.
+    fake_server
+        .request::<lsp::request::UnregisterCapability>(lsp::UnregistrationParams {
+            unregisterations: vec![lsp::Unregistration {
+                id: "first".to_string(),
+                method: "workspace/executeCommand".to_string(),
+            }],
+        })
+        .await
+        .into_response()
+        .unwrap();
+
+    let servers = lsp_store
+        .update(cx, |lsp_store, cx| {
+            lsp_store.workspace_commands(&buffer, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        servers
+            .into_iter()
+            .map(|server| server.commands)
+            .collect::<Vec<_>>(),
+        [vec!["initial".to_string(), "other".to_string()]]
+    );
+}
+
+#[gpui::test]
+async fn test_workspace_command_collects_every_applied_edit(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "a", "b.rs": "b" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let (language_registry, lsp_store) = project.read_with(cx, |project, _| {
+        (project.languages().clone(), project.lsp_store())
+    });
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                execute_command_provider: Some(lsp::ExecuteCommandOptions {
+                    commands: vec!["_the/command".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let _buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/a.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    // The server applies its edits to each file with a request of its own, including a file
+    // that isn't open.
+    fake_server.set_request_handler::<lsp::request::ExecuteCommand, _, _>({
+        let fake = fake_server.clone();
+        move |_, _| {
+            let fake = fake.clone();
+            async move {
+                for path in [path!("/dir/a.rs"), path!("/dir/b.rs")] {
+                    fake.server
+                        .request::<lsp::request::ApplyWorkspaceEdit>(
+                            lsp::ApplyWorkspaceEditParams {
+                                label: None,
+                                edit: lsp::WorkspaceEdit {
+                                    changes: Some(
+                                        [(
+                                            lsp::Url::from_file_path(path).unwrap(),
+                                            vec![lsp::TextEdit {
+                                                range: lsp::Range::new(
+                                                    lsp::Position::new(0, 0),
+                                                    lsp::Position::new(0, 0),
+                                                ),
+                                                new_text: "X".into(),
+                                            }],
+                                        )]
+                                        .into_iter()
+                                        .collect(),
+                                    ),
+                                    ..Default::default()
+                                },
+                            },
+                        )
+                        .await
+                        .into_response()
+                        .unwrap();
+                }
+                Ok(None)
+            }
+        }
+    });
+
+    let (transaction, _) = lsp_store
+        .update(cx, |lsp_store, cx| {
+            lsp_store.execute_workspace_command(
+                fake_server.server.server_id(),
+                "_the/command".to_string(),
+                Vec::new(),
+                None,
+                true,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    let mut edited_texts = Vec::new();
+    for buffer in transaction.0.keys() {
+        edited_texts.push(buffer.read_with(cx, |buffer, _| buffer.text()));
+    }
+    edited_texts.sort();
+    assert_eq!(edited_texts, ["Xa", "Xb"]);
+}
+
+#[gpui::test]
+fn test_merge_project_transactions(cx: &mut App) {
+    let buffer_a = cx.new(|cx| Buffer::local("a", cx));
+    let buffer_b = cx.new(|cx| Buffer::local("b", cx));
+    let edit = |buffer: &Entity<Buffer>, text: &'static str, cx: &mut App| {
+        buffer.update(cx, |buffer, cx| {
+            buffer.start_transaction();
+            let len = buffer.len();
+            buffer.edit([(len..len, text)], None, cx);
+            let transaction_id = buffer.end_transaction(cx).unwrap();
+            buffer.finalize_last_transaction();
+            buffer.get_transaction(transaction_id).unwrap().clone()
+        })
+    };
+
+    let mut transaction = ProjectTransaction::default();
+    transaction
+        .0
+        .insert(buffer_a.clone(), edit(&buffer_a, "1", cx));
+    let mut other = ProjectTransaction::default();
+    other.0.insert(buffer_a.clone(), edit(&buffer_a, "2", cx));
+    other.0.insert(buffer_b.clone(), edit(&buffer_b, "3", cx));
+    transaction.merge(other, cx);
+    assert_eq!(transaction.0.len(), 2);
+
+    buffer_a.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "a12");
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "a");
+    });
+}
+
 #[gpui::test]
 async fn test_single_file_worktrees_diagnostics(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -225,6 +225,10 @@ messages!(
     (GetDocumentColorResponse, Background),
     (GetColorPresentation, Background),
     (GetColorPresentationResponse, Background),
+    (GetLanguageServerCommands, Background),
+    (GetLanguageServerCommandsResponse, Background),
+    (ExecuteLanguageServerCommand, Background),
+    (ExecuteLanguageServerCommandResponse, Background),
     (RefreshCodeLens, Background),
     (GetCodeLens, Background),
     (GetCodeLensResponse, Background),
@@ -408,6 +412,11 @@ request_messages!(
     (ResolveInlayHint, ResolveInlayHintResponse),
     (GetDocumentColor, GetDocumentColorResponse),
     (GetColorPresentation, GetColorPresentationResponse),
+    (GetLanguageServerCommands, GetLanguageServerCommandsResponse),
+    (
+        ExecuteLanguageServerCommand,
+        ExecuteLanguageServerCommandResponse
+    ),
     (RespondToChannelInvite, Ack),
     (RespondToContactRequest, Ack),
     (SaveBuffer, BufferSaved),
@@ -579,6 +588,8 @@ entity_messages!(
     LspExtCancelFlycheck,
     LspExtRunFlycheck,
     LspExtClearFlycheck,
+    GetLanguageServerCommands,
+    ExecuteLanguageServerCommand,
     LanguageServerLog,
     Toast,
     HideToast,
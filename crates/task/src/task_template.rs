@@ -72,6 +72,11 @@ pub struct TaskTemplate {
     /// Whether to show the command line in the task output.
     #[serde(default = "default_true")]
     pub show_command: bool,
+    /// Whether `command` is the id of a workspace command of the active buffer's language servers,
+    /// such as `rust-analyzer.reloadWorkspace`, to execute instead of a program to spawn.
+    /// Each of `args` is sent as an argument of the command, parsed as JSON when it is valid JSON.
+    #[serde(default)]
+    pub lsp_command: bool,
 }
 
 #[derive(Deserialize, Eq, PartialEq, Clone, Debug)]
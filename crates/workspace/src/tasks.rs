@@ -1,6 +1,6 @@
 use std::process::ExitStatus;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use gpui::{AppContext, Context, Entity, Task};
 use language::Buffer;
 use project::{TaskSourceKind, WorktreeId};
@@ -55,6 +55,7 @@ impl Workspace {
         cx: &mut Context<Workspace>,
     ) {
         let spawn_in_terminal = resolved_task.resolved.clone();
+        let lsp_command = resolved_task.original_task().lsp_command;
         if !omit_history {
             if let Some(debugger_provider) = self.debugger_provider.as_ref() {
                 debugger_provider.task_scheduled(cx);
@@ -71,6 +72,28 @@ impl Workspace {
             });
         }
 
+        if lsp_command {
+            let Some(lsp_command_provider) = self.lsp_command_provider.clone() else {
+                self.show_error(
+                    &anyhow!(
+                        "no language server can run task commands here, so `{}` wasn't run",
+                        spawn_in_terminal.label
+                    ),
+                    cx,
+                );
+                return;
+            };
+            let Some(command) = spawn_in_terminal.command else {
+                self.show_error(
+                    &anyhow!("task `{}` has no command to run", spawn_in_terminal.label),
+                    cx,
+                );
+                return;
+            };
+            lsp_command_provider.execute_command(self, command, spawn_in_terminal.args, window, cx);
+            return;
+        }
+
         if let Some(terminal_provider) = self.terminal_provider.as_ref() {
             let task_status = terminal_provider.spawn(spawn_in_terminal, window, cx);
             let task = cx.background_spawn(async move {
@@ -614,6 +614,7 @@ pub fn main() {
         toolchain_selector::init(cx);
         theme_selector::init(cx);
         language_tools::init(cx);
+        lsp_workspace_command_selector::init(cx);
         call::init(app_state.client.clone(), app_state.user_store.clone(), cx);
         notifications::init(app_state.client.clone(), app_state.user_store.clone(), cx);
         collab_ui::init(&app_state, cx);
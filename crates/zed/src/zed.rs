@@ -358,6 +358,9 @@ pub fn initialize_workspace(
         );
         let active_buffer_language =
             cx.new(|_| language_selector::ActiveBufferLanguage::new(workspace));
+        let active_buffer_commands = cx.new(|cx| {
+            lsp_workspace_command_selector::ActiveBufferCommands::new(workspace, window, cx)
+        });
         let active_toolchain_language =
             cx.new(|cx| toolchain_selector::ActiveToolchain::new(workspace, window, cx));
         let vim_mode_indicator = cx.new(|cx| vim::ModeIndicator::new(window, cx));
@@ -380,6 +383,7 @@ pub fn initialize_workspace(
             status_bar.add_left_item(diagnostic_summary, window, cx);
             status_bar.add_left_item(activity_indicator, window, cx);
             status_bar.add_right_item(edit_prediction_button, window, cx);
+            status_bar.add_right_item(active_buffer_commands, window, cx);
             status_bar.add_right_item(active_buffer_language, window, cx);
             status_bar.add_right_item(active_toolchain_language, window, cx);
             status_bar.add_right_item(vim_mode_indicator, window, cx);
@@ -4350,6 +4354,7 @@ mod tests {
                 "keystroke_input",
                 "language_selector",
                 "lsp_tool",
+                "lsp_workspace_command_selector",
                 "markdown",
                 "menu",
                 "notebook",
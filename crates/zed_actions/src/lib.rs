@@ -216,6 +216,18 @@ pub mod command_palette {
     );
 }
 
+pub mod lsp_workspace_command_selector {
+    use gpui::actions;
+
+    actions!(
+        lsp_workspace_command_selector,
+        [
+            /// Toggles the LSP workspace command selector modal.
+            Toggle
+        ]
+    );
+}
+
 pub mod feedback {
     use gpui::actions;
 